@@ -0,0 +1,2 @@
+-- Thresholds used to be stored in days, from now on they are stored in seconds.
+UPDATE alerts SET threshold = threshold * 86400;
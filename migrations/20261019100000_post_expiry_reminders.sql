@@ -0,0 +1,4 @@
+-- Optional reminders after expiry, and the reminder stage reached by each alert.
+-- Stage 0 means the alert is waiting for its threshold, stage N means N notifications have been sent.
+ALTER TABLE alerts ADD COLUMN post_expiry BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE alerts ADD COLUMN stage INTEGER NOT NULL DEFAULT 0;
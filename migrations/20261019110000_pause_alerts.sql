@@ -0,0 +1,2 @@
+-- Alerts can be muted until a given unix timestamp. NULL means the alert is not paused.
+ALTER TABLE alerts ADD COLUMN paused_until INTEGER;
@@ -0,0 +1,5 @@
+-- Per-guild bot settings. Guilds without a row use the defaults.
+CREATE TABLE guild_settings (
+    guild_id INTEGER PRIMARY KEY,
+    public_replies BOOLEAN NOT NULL DEFAULT 0
+);
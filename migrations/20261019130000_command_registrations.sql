@@ -0,0 +1,6 @@
+-- Last slash command definitions registered on discord, by scope ("global" or "guild:<id>").
+-- Used to skip the registration when nothing changed since the previous start.
+CREATE TABLE command_registrations (
+    scope TEXT PRIMARY KEY,
+    definition TEXT NOT NULL
+);
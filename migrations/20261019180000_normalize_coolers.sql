@@ -0,0 +1,14 @@
+-- Coolers are now stored lowercase, so that the same contract typed with a different case is the same cooler.
+-- Drop the alerts whose cooler isn't an address at all, since they could never be monitored.
+DELETE FROM alerts
+WHERE length(cooler) != 42
+    OR lower(substr(cooler, 1, 2)) != '0x'
+    OR lower(substr(cooler, 3)) GLOB '*[^0-9a-f]*';
+
+-- Merge the alerts that only differed by the case of their cooler, keeping the most recent copy.
+DELETE FROM alerts WHERE alert_id NOT IN (
+    SELECT MAX(alert_id) FROM alerts
+    GROUP BY user_id, lower(cooler), loan_id, COALESCE(webhook_hash, ''), COALESCE(email_hash, '')
+);
+
+UPDATE alerts SET cooler = lower(cooler);
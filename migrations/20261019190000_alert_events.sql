@@ -0,0 +1,20 @@
+-- Append-only history of the changes to the alerts, to find out who changed or deleted an alert and when.
+-- The alert is copied into each entry, so that the history outlives the alert.
+CREATE TABLE alert_events (
+    event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    alert_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    guild_id INTEGER,
+    cooler TEXT NOT NULL,
+    loan_id INTEGER NOT NULL,
+    -- NULL when the change was made by the bot itself.
+    actor_id INTEGER,
+    source TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    detail TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX alert_events_alert ON alert_events (alert_id);
+CREATE INDEX alert_events_user ON alert_events (user_id);
+CREATE INDEX alert_events_guild ON alert_events (guild_id);
@@ -0,0 +1,25 @@
+-- Schema of the Postgres backend, matching the SQLite migrations up to this point.
+CREATE TABLE alerts (
+    alert_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    cooler TEXT NOT NULL,
+    loan_id BIGINT NOT NULL,
+    -- Threshold in seconds.
+    threshold BIGINT NOT NULL,
+    webhook_url TEXT,
+    email TEXT,
+    active BOOLEAN NOT NULL,
+    post_expiry BOOLEAN NOT NULL DEFAULT FALSE,
+    stage BIGINT NOT NULL DEFAULT 0,
+    paused_until BIGINT
+);
+
+CREATE TABLE guild_settings (
+    guild_id BIGINT PRIMARY KEY,
+    public_replies BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE command_registrations (
+    scope TEXT PRIMARY KEY,
+    definition TEXT NOT NULL
+);
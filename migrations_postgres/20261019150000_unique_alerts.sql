@@ -0,0 +1,8 @@
+-- Merge the alerts that share a user, cooler, loan and destination, keeping the most recent copy.
+DELETE FROM alerts WHERE alert_id NOT IN (
+    SELECT MAX(alert_id) FROM alerts
+    GROUP BY user_id, cooler, loan_id, COALESCE(webhook_url, ''), COALESCE(email, '')
+);
+
+-- Receivers are nullable, so they are coalesced for missing destinations to compare equal.
+CREATE UNIQUE INDEX alerts_destination ON alerts (user_id, cooler, loan_id, COALESCE(webhook_url, ''), COALESCE(email, ''));
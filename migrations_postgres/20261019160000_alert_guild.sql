@@ -0,0 +1,3 @@
+-- Guild where the alert was created, used for the per-guild quota. NULL for alerts created in DMs or before this migration.
+ALTER TABLE alerts ADD COLUMN guild_id BIGINT;
+CREATE INDEX alerts_guild ON alerts (guild_id);
@@ -0,0 +1,13 @@
+-- Webhook URLs and emails are now encrypted by the bot. Since equal values no longer have equal ciphertexts,
+-- alerts are kept unique on a keyed hash of their receivers, and a masked version is kept to show them to the user.
+ALTER TABLE alerts ADD COLUMN webhook_hash TEXT;
+ALTER TABLE alerts ADD COLUMN email_hash TEXT;
+ALTER TABLE alerts ADD COLUMN webhook_hint TEXT;
+ALTER TABLE alerts ADD COLUMN email_hint TEXT;
+
+-- Existing receivers stay in plaintext until `cooler-bot reencrypt` runs.
+-- Until then they are their own hash, which keeps them unique as before.
+UPDATE alerts SET webhook_hash = webhook_url, email_hash = email;
+
+DROP INDEX alerts_destination;
+CREATE UNIQUE INDEX alerts_destination ON alerts (user_id, cooler, loan_id, COALESCE(webhook_hash, ''), COALESCE(email_hash, ''));
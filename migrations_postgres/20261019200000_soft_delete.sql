@@ -0,0 +1,7 @@
+-- Deleted alerts are kept for a while, so that a mistaken deletion can be undone.
+ALTER TABLE alerts ADD COLUMN deleted_at BIGINT;
+CREATE INDEX alerts_deleted ON alerts (deleted_at);
+
+-- Only the alerts that are not deleted must stay unique, so that a deleted alert can be created again.
+DROP INDEX alerts_destination;
+CREATE UNIQUE INDEX alerts_destination ON alerts (user_id, cooler, loan_id, COALESCE(webhook_hash, ''), COALESCE(email_hash, '')) WHERE deleted_at IS NULL;
@@ -0,0 +1,42 @@
+use ethers::types::Address;
+use ethers::utils::to_checksum;
+
+// Parses a user provided Cooler address. The case of the letters doesn't matter.
+pub fn parse_address(input: &str) -> Result<Address, String> {
+    let input = input.trim();
+    match input.len() == 42 && input.to_lowercase().starts_with("0x") {
+        true => input.parse().map_err(|_| format!("`{input}` is not a valid address.")),
+        false => Err(format!("`{input}` is not a valid address. Addresses start with `0x` followed by 40 hexadecimal characters.")),
+    }
+}
+
+// Form in which addresses are stored: lowercase and `0x` prefixed, so that equal addresses are equal strings.
+pub fn canonical_address(address: &Address) -> String {
+    format!("{address:?}")
+}
+
+// Form in which addresses are shown to the user, with the EIP-55 checksum.
+pub fn format_address(address: &Address) -> String {
+    to_checksum(address, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_case_gives_the_same_address() {
+        let lower = parse_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let mixed = parse_address(" 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed ").unwrap();
+        assert_eq!(lower, mixed);
+        assert_eq!(canonical_address(&mixed), "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed");
+        assert_eq!(format_address(&lower), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+    }
+
+    #[test]
+    fn rejects_what_is_not_an_address() {
+        assert!(parse_address("0xabc").is_err());
+        assert!(parse_address("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed00").is_err());
+        assert!(parse_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::error::Error;
+use crate::registry::{self, crypto::FieldCipher, error::RegistryError, Registry};
+use crate::registry::types::{Actor, AlertUpdate};
+
+// Maintenance commands, run as `cooler-bot <command> [arguments]` while the bot is stopped.
+pub async fn run(command: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let registry = registry::connect(&registry::database_url()).await?;
+    match (command, args) {
+        ("reencrypt", []) => reencrypt(&registry, &FieldCipher::from_env()).await,
+        ("forget_user", [user_id]) => forget_user(&registry, user_id.parse().map_err(|_| format!("Invalid user ID `{user_id}`."))?).await,
+        _ => Err(format!("Unknown command `{command}`. Available commands: reencrypt, forget_user <discord user ID>").into()),
+    }
+}
+
+// Seals every receiver with the current key: those still in plaintext, and those sealed with an old key
+// before a rotation. Once it is done, the old keys can be removed from `ENCRYPTION_OLD_KEYS`.
+// The bot runs it on startup too, as plaintext receivers are only kept unique on their hash once sealed.
+pub async fn reencrypt(registry: &Registry, cipher: &FieldCipher) -> Result<(), Box<dyn Error>> {
+    let (mut resealed, mut merged) = (0, 0);
+    for alert in registry.get_all_alerts().await? {
+        let webhook_url = match alert.get_encrypted_webhook_url() {
+            Some(stored) if !cipher.is_current(stored) => Some(cipher.seal_webhook_url(&cipher.open(stored)?)),
+            _ => None,
+        };
+        let email = match alert.get_encrypted_email() {
+            Some(stored) if !cipher.is_current(stored) => Some(cipher.seal_email(&cipher.open(stored)?)),
+            _ => None,
+        };
+        if webhook_url.is_none() && email.is_none() {
+            continue;
+        }
+
+        match registry.update_alert(alert.get_user_id(), alert.get_alert_id(), AlertUpdate { webhook_url, email, ..AlertUpdate::default() }, Actor::CLI).await {
+            Ok(_) => resealed += 1,
+            // The bot may have accepted a copy of this alert since the key changed, as their hashes didn't match.
+            // Alerts are visited in creation order, so the copy is the most recent one and is kept.
+            Err(RegistryError::Duplicate) => {
+                registry.delete_alert(alert.get_user_id(), alert.get_alert_id(), Actor::CLI).await?;
+                merged += 1;
+            },
+            Err(error) => return Err(error.into()),
+        }
+    }
+    println!("Re-encrypted {resealed} alerts and merged {merged} duplicates.");
+    Ok(())
+}
+
+// Erases everything stored about a user, like /forget_me does, for requests received outside of discord.
+async fn forget_user(registry: &Registry, user_id: i64) -> Result<(), Box<dyn Error>> {
+    let data = registry.get_user_data(user_id).await?;
+    let forgotten = registry.forget_user(user_id).await?;
+    println!(
+        "Erased {} alerts ({} of them deleted) and {} history entries of user {user_id}.",
+        forgotten.alerts, data.deleted_alerts, forgotten.events,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use ethers::types::Address;
+    use crate::registry::{crypto::SealedField, memory::MemoryStore};
+    use crate::registry::types::{AlertFilter, AlertQuota, CreatedAlert, NewAlert};
+
+    #[tokio::test]
+    async fn plaintext_receivers_are_sealed_and_deduplicated() {
+        let registry: Registry = Arc::new(MemoryStore::default());
+        let cipher = FieldCipher::new(&[1; 32], &[]);
+        let quota = AlertQuota { per_user: 10, per_guild: 10 };
+        let alert = |email: SealedField| NewAlert {
+            user_id: 1,
+            cooler: Address::repeat_byte(0xab),
+            loan_id: 0,
+            threshold: 3600,
+            post_expiry: false,
+            webhook_url: None,
+            email: Some(email),
+            guild_id: None,
+        };
+        // Stored by a previous version, with the plaintext as its own hash like the migration left it.
+        let legacy = SealedField { ciphertext: "a@b.c".to_string(), blind_index: "a@b.c".to_string(), hint: "a***@b.c".to_string() };
+        let CreatedAlert::New(legacy_id) = registry.create_alert(alert(legacy), &quota, Actor::CLI).await.unwrap() else { panic!() };
+        let CreatedAlert::New(copy_id) = registry.create_alert(alert(cipher.seal_email("a@b.c")), &quota, Actor::CLI).await.unwrap() else { panic!() };
+
+        reencrypt(&registry, &cipher).await.unwrap();
+        let alerts = registry.get_user_alerts(1, &AlertFilter::default(), 10, 0).await.unwrap();
+        assert_eq!(alerts.iter().map(|alert| alert.get_alert_id()).collect::<Vec<_>>(), vec![copy_id]);
+        assert!(matches!(registry.get_user_alert(1, legacy_id).await, Err(RegistryError::NotFound)));
+        assert!(cipher.is_current(alerts[0].get_encrypted_email().unwrap()));
+        // Creating it again now updates the remaining alert.
+        let again = registry.create_alert(alert(cipher.seal_email("a@b.c")), &quota, Actor::CLI).await.unwrap();
+        assert_eq!(again, CreatedAlert::Updated(copy_id));
+    }
+}
@@ -0,0 +1,114 @@
+use ethers::types::Address;
+use crate::address::{format_address, parse_address};
+use crate::duration::{format_exact_duration, parse_duration};
+use crate::registry::crypto::FieldCipher;
+use crate::registry::types::AlertDB;
+
+// Columns of the files written by /export_alerts and read by /import_alerts.
+// Receivers are exported encrypted, and can be replaced by plain webhook URLs or emails before importing.
+pub const HEADER: &str = "cooler,loan_id,threshold,post_expiry,webhook_url,email";
+
+// Largest file accepted by /import_alerts, well above what the alert quota allows.
+pub const MAX_FILE_SIZE: u64 = 64 * 1024;
+
+// Most rows imported at once, as each of them may have to reach a webhook.
+pub const MAX_ROWS: usize = 100;
+
+// Alert read from a row of an imported file.
+#[derive(Debug, PartialEq)]
+pub struct ImportedAlert {
+    pub cooler: Address,
+    pub loan_id: i64,
+    // Threshold in seconds.
+    pub threshold: i64,
+    pub post_expiry: bool,
+    // Encrypted as exported, or in plaintext when written by hand.
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+}
+
+// Writes the alerts as CSV, one row per alert. Receivers never leave in plaintext, even those not sealed yet.
+pub fn write_alerts(alerts: &[AlertDB], cipher: &FieldCipher) -> String {
+    let sealed = |stored: Option<&str>| stored.map(|stored| cipher.ensure_sealed(stored)).unwrap_or_default();
+    let mut file = format!("{HEADER}\n");
+    for alert in alerts {
+        file.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            format_address(&alert.get_cooler()),
+            alert.get_loan_id(),
+            format_exact_duration(alert.get_threshold()),
+            alert.has_post_expiry(),
+            sealed(alert.get_encrypted_webhook_url()),
+            sealed(alert.get_encrypted_email()),
+        ));
+    }
+    file
+}
+
+// Row of an imported file: its line number, and the alert or why it is invalid.
+pub type ImportedRow = (usize, Result<ImportedAlert, String>);
+
+// Reads the rows of a CSV file, each with its line number. A wrong header rejects the whole file,
+// while an invalid row only fails on its own.
+pub fn read_alerts(file: &str) -> Result<Vec<ImportedRow>, String> {
+    let mut lines = file.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    match lines.next() {
+        Some((_, header)) if header.trim().trim_start_matches('\u{feff}') == HEADER => (),
+        _ => return Err(format!("The first line of the file must be `{HEADER}`.")),
+    }
+    Ok(lines.map(|(index, line)| (index + 1, read_row(line))).collect())
+}
+
+fn read_row(line: &str) -> Result<ImportedAlert, String> {
+    // Spreadsheets may quote every cell, none of the columns can contain a comma.
+    let cells: Vec<&str> = line.split(',').map(|cell| cell.trim().trim_matches('"').trim()).collect();
+    let [cooler, loan_id, threshold, post_expiry, webhook_url, email] = cells[..] else {
+        return Err(format!("Expected 6 columns, found {}.", cells.len()));
+    };
+    let optional = |cell: &str| Some(cell.to_string()).filter(|cell| !cell.is_empty());
+    Ok(ImportedAlert {
+        cooler: parse_address(cooler).map_err(|error| format!("Invalid cooler: {error}"))?,
+        loan_id: loan_id.parse().ok().filter(|loan_id| *loan_id >= 0).ok_or(format!("Invalid loan ID: `{loan_id}`."))?,
+        threshold: parse_duration(threshold)?,
+        post_expiry: match post_expiry.to_lowercase().as_str() {
+            "true" | "yes" | "1" => true,
+            "false" | "no" | "0" | "" => false,
+            _ => return Err(format!("Invalid post_expiry: `{post_expiry}`, expected `true` or `false`.")),
+        },
+        webhook_url: optional(webhook_url),
+        email: optional(email),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_rows_independently() {
+        let file = format!(
+            "{HEADER}\n0xabababababababababababababababababababab,3,1d12h,true,,enc:v1:0011:AAAA\n\n0xab,1,2d,false,,a@b.c\n\"0xdededededededededededededededededededede\",\"0\",\"90m\",\"no\",\"https://discord.com/api/webhooks/1/a\",\"\"\n"
+        );
+        let rows = read_alerts(&file).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], (2, Ok(ImportedAlert {
+            cooler: Address::repeat_byte(0xab),
+            loan_id: 3,
+            threshold: 36 * 3600,
+            post_expiry: true,
+            webhook_url: None,
+            email: Some("enc:v1:0011:AAAA".to_string()),
+        })));
+        assert_eq!(rows[1].0, 4);
+        assert!(rows[1].1.as_ref().unwrap_err().starts_with("Invalid cooler"));
+        assert_eq!(rows[2].1.as_ref().unwrap().threshold, 90 * 60);
+        assert!(read_alerts("cooler,loan_id\n").is_err());
+    }
+
+    #[test]
+    fn thresholds_are_written_exactly() {
+        for threshold in [0, 59, 36 * 3600, 8 * 24 * 3600 + 61] {
+            assert_eq!(parse_duration(&format_exact_duration(threshold)), Ok(threshold));
+        }
+    }
+}
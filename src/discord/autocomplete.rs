@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use std::time::Duration;
+use serenity::json::Value;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::prelude::*;
+use ethers::{
+    providers::{Http, Provider},
+    types::Address,
+};
+use crate::address::format_address;
+use crate::discord::options::Options;
+use crate::duration::format_timestamp;
+use crate::listener::loans::{get_open_loans, LoanStatus};
+use crate::registry::Registry;
+
+// Discord doesn't show more suggestions than this.
+const MAX_CHOICES: usize = 25;
+
+// Discord drops the suggestions if they take longer than 3 seconds, so reading the open loans
+// on-chain is given up after this long.
+const OPEN_LOANS_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Commands whose `loan_id` may refer to a loan without alerts yet.
+const OPEN_LOANS_COMMANDS: [&str; 2] = ["create_alert", "loan_status"];
+
+enum Choices {
+    Coolers(Vec<String>),
+    Loans(Vec<(i64, String)>),
+}
+
+// Suggests values for the `cooler` and `loan_id` options while the user types them.
+pub async fn handle(ctx: &Context, registry: &Registry, provider: Arc<Provider<Http>>, autocomplete: &AutocompleteInteraction) {
+    let user_id = autocomplete.user.id.0 as i64;
+    let focused = match autocomplete.data.options.iter().find(|option| option.focused) {
+        Some(option) => option,
+        None => return,
+    };
+    // Partial input is sent as it has been typed, even for integer options.
+    let typed = match &focused.value {
+        Some(Value::String(typed)) => typed.trim().to_lowercase(),
+        Some(Value::Number(typed)) => typed.to_string(),
+        _ => String::new(),
+    };
+
+    let choices = match focused.name.as_str() {
+        "cooler" => {
+            let coolers = registry.get_user_coolers(user_id).await.unwrap_or_default();
+            Choices::Coolers(coolers
+                .iter()
+                .map(format_address)
+                .filter(|cooler| cooler.to_lowercase().contains(&typed))
+                .collect())
+        },
+        "loan_id" => {
+            let cooler = match Options::new(&autocomplete.data.options).address("cooler") {
+                Ok(Some(cooler)) => cooler,
+                _ => return respond(ctx, autocomplete, Choices::Loans(Vec::new())).await,
+            };
+            let with_open_loans = OPEN_LOANS_COMMANDS.contains(&autocomplete.data.name.as_str());
+            let loans = loan_choices(registry, provider, user_id, cooler, with_open_loans).await;
+            Choices::Loans(loans.into_iter().filter(|(loan_id, _)| loan_id.to_string().starts_with(&typed)).collect())
+        },
+        _ => return,
+    };
+    respond(ctx, autocomplete, choices).await;
+}
+
+// Loans of the Cooler the user already has alerts for, followed by its other open loans.
+async fn loan_choices(registry: &Registry, provider: Arc<Provider<Http>>, user_id: i64, cooler: Address, with_open_loans: bool) -> Vec<(i64, String)> {
+    let mut loans: Vec<(i64, String)> = registry.get_user_loan_ids(user_id, cooler)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|loan_id| (loan_id, format!("Loan {loan_id} · has alerts")))
+        .collect();
+
+    if !with_open_loans {
+        return loans;
+    }
+    if let Ok(Ok(open_loans)) = tokio::time::timeout(OPEN_LOANS_TIMEOUT, get_open_loans(provider, cooler)).await {
+        for (loan_id, loan) in open_loans {
+            if loans.iter().any(|(known, _)| *known == loan_id) {
+                continue;
+            }
+            let expiry = match loan.status {
+                LoanStatus::Expired => format!("expired on {}", format_timestamp(loan.expiry)),
+                _ => format!("expires on {}", format_timestamp(loan.expiry)),
+            };
+            loans.push((loan_id, format!("Loan {loan_id} · {expiry}")));
+        }
+    }
+    loans
+}
+
+async fn respond(ctx: &Context, autocomplete: &AutocompleteInteraction, choices: Choices) {
+    if let Err(why) = autocomplete
+        .create_autocomplete_response(&ctx.http, |response| {
+            match choices {
+                Choices::Coolers(coolers) => coolers.iter().take(MAX_CHOICES).for_each(|cooler| {
+                    response.add_string_choice(cooler, cooler);
+                }),
+                Choices::Loans(loans) => loans.iter().take(MAX_CHOICES).for_each(|(loan_id, name)| {
+                    response.add_int_choice(name, *loan_id);
+                }),
+            };
+            response
+        })
+        .await
+    {
+        println!("Cannot respond to autocomplete: {}", why);
+    }
+}
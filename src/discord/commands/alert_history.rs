@@ -0,0 +1,103 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::Permissions;
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::types::{AlertEvent, EventFilter};
+use crate::registry::Registry;
+
+// Entries shown at once, which keeps the embed under the 4096 characters allowed by discord.
+const HISTORY_SIZE: i64 = 20;
+
+// Options of the /alert_history command.
+#[derive(Debug, Default, PartialEq)]
+pub struct AlertHistoryOptions {
+    pub alert_id: Option<i64>,
+    // Owner of the alerts.
+    pub user_id: Option<i64>,
+}
+
+impl FromOptions for AlertHistoryOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self { alert_id: options.i64("alert_id")?, user_id: options.user_id("user")? })
+    }
+}
+
+pub async fn run(registry: &Registry, caller_id: i64, guild_id: Option<i64>, options: &[CommandDataOption]) -> CreateEmbed {
+    let AlertHistoryOptions { alert_id, user_id } = match AlertHistoryOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    // Server managers only get to see the alerts created in their server, while in direct messages users see
+    // the history of their own alerts, wherever they were created. Alerts created in DMs can only be seen there.
+    let (filter, scope) = match guild_id {
+        Some(guild_id) => (EventFilter { alert_id, user_id, guild_id: Some(guild_id) }, "of this server"),
+        None if user_id.is_some_and(|user_id| user_id != caller_id) => {
+            return CreateEmbed::default()
+            .title("Not available in direct messages")
+            .description("Only your own alerts can be shown here. The history of other users is available to server managers.")
+            .color(0xDB4B4B)
+            .to_owned();
+        },
+        None => (EventFilter { alert_id, user_id: Some(caller_id), guild_id: None }, "you own"),
+    };
+    match registry.get_alert_events(&filter, HISTORY_SIZE).await {
+        Ok(events) if events.is_empty() => CreateEmbed::default()
+            .title("No history found")
+            .description(format!("No alert {scope} matches the given filters."))
+            .color(0xDB4B4B)
+            .to_owned(),
+        Ok(events) => CreateEmbed::default()
+            .title("Alert history")
+            .description(events.iter().map(event_line).collect::<Vec<_>>().join("\n"))
+            .footer(|f| f.text(format!("Showing the last {} changes to the alerts {scope}, most recent first.", events.len())))
+            .color(0xC7D5E8)
+            .to_owned(),
+        Err(error) => registry_error_embed(&error, "Unable to read the alert history. Please try again."),
+    }
+}
+
+// One-line summary of a change.
+fn event_line(event: &AlertEvent) -> String {
+    let actor = match event.actor_id {
+        Some(actor_id) => format!("<@{actor_id}>"),
+        None => "the bot".to_string(),
+    };
+    let detail = match &event.detail {
+        Some(detail) => format!(" ({detail})"),
+        None => String::new(),
+    };
+    format!(
+        "<t:{}:f> · **#{}** · Loan {} of <@{}> · {}{detail} by {actor} via {}",
+        event.created_at, event.alert_id, event.loan_id, event.user_id, event.kind, event.source,
+    )
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("alert_history").description("Show who changed the alerts of this server, or your own in DMs, and when")
+        // In servers the history shows every user's alerts, so it is kept to server managers.
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .create_option(|option| {
+            option
+                .name("alert_id")
+                .description("Only show the history of this alert.")
+                .kind(CommandOptionType::Integer)
+                .required(false)
+                .min_int_value(0)
+        })
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("Only show the history of the alerts of this user.")
+                .kind(CommandOptionType::User)
+                .required(false)
+        })
+}
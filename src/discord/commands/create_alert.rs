@@ -1,24 +1,50 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use serenity::builder::{
     CreateApplicationCommand,
+    CreateComponents,
     CreateEmbed,
 };
+use serenity::model::application::component::InputTextStyle;
+use serenity::model::application::interaction::{
+    application_command::ApplicationCommandInteraction,
+    message_component::MessageComponentInteraction,
+    modal::ModalSubmitInteraction,
+    InteractionResponseType,
+};
 use serenity::model::prelude::{
     command::CommandOptionType,
     application_command::CommandDataOption,
 };
-use crate::registry::utils::{
-    count_user_alerts,
-    create_alert,
+use serenity::prelude::*;
+use ethers::{
+    providers::{Http, Provider},
+    types::Address,
 };
+use crate::address::{format_address, parse_address};
+use crate::duration::{format_timestamp, parse_duration};
+use crate::discord::components::modal_value;
+use crate::discord::errors::registry_error_embed;
+use crate::discord::limits::{cooldown_embed, Limits};
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::listener::loans::{get_open_loans, LoanStatus};
+use crate::discord::validation::{receivers_error_embed, validate_receivers};
+use crate::discord::commands::list_alerts::format_threshold;
+use crate::registry::Registry;
+use crate::registry::crypto::FieldCipher;
+use crate::registry::types::{Actor, CreatedAlert, EventSource, NewAlert};
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("create_alert").description("Create a new alert")
+    // Every option is optional so that the command can be sent empty to open the guided form instead.
+    command.name("create_alert").description("Create a new alert, or run it without options to fill a form")
         .create_option(|option| {
             option
                 .name("cooler")
                 .description("The address of the Cooler contract. Must starts with `0x`.")
                 .kind(CommandOptionType::String)
-                .required(true)
+                .required(false)
+                .set_autocomplete(true)
                 .min_length(42) // enforce length of EVM address.
                 .max_length(42) // enforce length of EVM address.
         })
@@ -27,16 +53,16 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 .name("loan_id")
                 .description("The ID of the loan to be monitored.")
                 .kind(CommandOptionType::Integer)
-                .required(true)
+                .required(false)
+                .set_autocomplete(true)
                 .min_int_value(0)
         })
         .create_option(|option| {
             option
                 .name("threshold")
-                .description("The days before expiration that the user should be notified in advance.")
-                .kind(CommandOptionType::Integer)
-                .required(true)
-                .min_int_value(0)
+                .description("How long before expiration the user should be notified. Examples: 36h, 2d, 1w.")
+                .kind(CommandOptionType::String)
+                .required(false)
         })
         .create_option(|option| {
             option
@@ -52,146 +78,426 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 .kind(CommandOptionType::String)
                 .required(false)
         })
+        .create_option(|option| {
+            option
+                .name("post_expiry")
+                .description("Keep reminding after expiry until the loan is repaid or claimed.")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
 }
 
-pub async fn run(database: &sqlx::SqlitePool, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
-    let alerts_prev = match count_user_alerts(database, user_id).await {
-        Ok(alerts) => alerts,
-        Err(error) => {
-            return CreateEmbed::default()
-            .title("Something went wrong!")
-            .description("Unable to retrieve current alerts form the DB. Please try again.")
-            .field("Error", error.to_string(), false)
-            .color(0xDB4B4B)
-            .to_owned();
-        }
-    };
+// Options of the /create_alert command.
+#[derive(Debug, PartialEq)]
+pub struct CreateAlertOptions {
+    pub cooler: Address,
+    pub loan_id: i64,
+    // Threshold in seconds.
+    pub threshold: i64,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+    pub post_expiry: bool,
+}
+
+impl FromOptions for CreateAlertOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        let threshold = parse_duration(options.required_str("threshold")?)
+            .map_err(|reason| OptionError::Invalid { name: "threshold", reason })?;
+
+        Ok(Self {
+            cooler: options.required_address("cooler")?,
+            loan_id: options.required_i64("loan_id")?,
+            threshold,
+            webhook_url: options.str("webhook_url")?.map(|webhook_url| webhook_url.to_string()),
+            email: options.str("email")?.map(|email| email.to_string()),
+            post_expiry: options.bool("post_expiry")?.unwrap_or(false),
+        })
+    }
+}
+
+// Everything needed to store an alert besides the user input.
+pub struct CreateContext<'a> {
+    pub registry: &'a Registry,
+    pub cipher: &'a FieldCipher,
+    pub limits: &'a Limits,
+}
+
+pub async fn run(context: &CreateContext<'_>, user_id: i64, guild_id: Option<i64>, options: &[CommandDataOption]) -> CreateEmbed {
+    match CreateAlertOptions::from_options(options) {
+        Ok(options) => create(context, user_id, guild_id, EventSource::Command, options).await,
+        Err(error) => error.embed(),
+    }
+}
+
+// Validates the receivers and registers the alert, shared by the slash command and the guided form.
+pub async fn create(context: &CreateContext<'_>, user_id: i64, guild_id: Option<i64>, source: EventSource, options: CreateAlertOptions) -> CreateEmbed {
+    let CreateContext { registry, cipher, limits } = context;
+    if let Err(remaining) = limits.cooldown.check(user_id) {
+        return cooldown_embed(remaining);
+    }
 
     // Process the alert parameters.
-    let cooler = match &options.get(0).expect("Expected cooler").value {
-        Some(cooler) => match cooler.as_str() {
-            Some(cooler) => cooler,
-            None => panic!("Expected cooler"),
-        },
-        None => panic!("Expected cooler"),    
-    };
+    let CreateAlertOptions { cooler, loan_id, threshold, webhook_url, email, post_expiry } = options;
+    if webhook_url.is_none() && email.is_none() {
+        return missing_receiver_embed();
+    }
 
-    let loan_id = match &options.get(1).expect("Expected loan_id").value {
-        Some(loan_id) => match loan_id.as_i64() {
-            Some(loan_id) => loan_id,
-            None => panic!("Expected loan_id"),
-        },
-        None => panic!("Expected loan_id"),
+    let (webhook_url, email) = match validate_receivers(webhook_url.as_deref(), email.as_deref()).await {
+        Ok(receivers) => receivers,
+        Err(errors) => return receivers_error_embed(errors),
+    };
+    let check = |enabled: bool| match enabled {
+        true => ":white_check_mark:",
+        false => ":x:",
     };
+    let (webhook_check, email_check) = (check(webhook_url.is_some()), check(email.is_some()));
 
-    let threshold = match &options.get(2).expect("Expected threshold").value {
-        Some(threshold) => match threshold.as_i64() {
-            Some(threshold) => threshold,
-            None => panic!("Expected threshold"),
-        },
-        None => panic!("Expected threshold"),
+    let webhook_url = webhook_url.map(|webhook_url| cipher.seal_webhook_url(&webhook_url));
+    let email = email.map(|email| cipher.seal_email(&email));
+    let alert = NewAlert { user_id, cooler, loan_id, threshold, post_expiry, webhook_url, email, guild_id };
+    let actor = Actor { user_id: Some(user_id), source };
+    let created = registry.create_alert(alert, &limits.quota, actor).await;
+    if created.is_ok() {
+        limits.cooldown.record(user_id);
+    }
+    let title = match created {
+        Ok(CreatedAlert::New(_)) => "Alert successfully added",
+        Ok(CreatedAlert::Updated(_)) => "Alert already exists — updated threshold",
+        Err(error) => return registry_error_embed(&error, "Error when trying to register the alerts in the database. Please try again."),
     };
 
-    let mut email: Option<String> = None;
-    let mut webhook_url: Option<String> = None;
-    match &options.get(3) {
-        Some(receiver) => {
-            match &receiver.value {
-                Some(receiver) => match receiver.as_str() {
-                    Some(receiver) => {
-                        if receiver.contains("https://") || receiver.contains("http://") {
-                            webhook_url = Some(receiver.to_string());
-                        } else if receiver.contains("@") {
-                            email = Some(receiver.to_string());
-                        } else {
-                            return CreateEmbed::default()
-                            .title("Something went wrong!")
-                            .description("Invalid webhoook or email. Please try again with a valid input format.")
-                            .color(0xDB4B4B)
-                            .to_owned();
-                        }
-                    },
-                    None => (),
-                },
-                None => (),
-            };
-        },
-        None => return CreateEmbed::default()
+    // Return the success embed.
+    let cooler = format_address(&cooler);
+    CreateEmbed::default()
+        .title(title)
+        .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
+        .field("Loan ID", loan_id, true)
+        .field("", "", true)
+        .field("Alert threshold", format_threshold(threshold, post_expiry), true)
+        .field("Webhook Notification?", webhook_check, true)
+        .field("", "", true)
+        .field("Email Notification?", email_check, true)
+        .field("", "", false)
+        .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+        .color(0x2AC3DE)
+        .to_owned()
+}
+
+fn missing_receiver_embed() -> CreateEmbed {
+    CreateEmbed::default()
         .title("Missing alert receiver!")
         .description("Please try again informing a webhook URL or an email.")
         .color(0xDB4B4B)
         .to_owned()
+}
+
+// Custom IDs of the guided form and of the loan picker that follows it.
+pub const MODAL_ID: &str = "create_alert";
+pub const LOAN_SELECT_ID: &str = "create_alert:loan";
+
+// Discord doesn't allow more options in a select menu.
+const MAX_LOAN_CHOICES: usize = 25;
+
+// How long a form waits for the user to pick a loan before it is dropped.
+const PENDING_ALERT_TTL: Duration = Duration::from_secs(10 * 60);
+
+// Forms waiting for the user to pick a loan, by user ID. The webhook URL doesn't fit in a custom ID,
+// so the rest of the form is kept here until the select menu is used.
+#[derive(Default)]
+pub struct PendingAlerts(Mutex<HashMap<i64, (Instant, CreateAlertOptions)>>);
+
+impl PendingAlerts {
+    // Keeps the form of the user, replacing the one they may have left unfinished.
+    fn insert(&self, user_id: i64, options: CreateAlertOptions) {
+        self.lock().insert(user_id, (Instant::now(), options));
+    }
+
+    // Takes back the form of the user, unless it expired.
+    pub fn take(&self, user_id: i64) -> Option<CreateAlertOptions> {
+        self.lock().remove(&user_id).map(|(_, options)| options)
+    }
+
+    // Forms whose menu was never used would stay forever, so the expired ones are dropped on every access.
+    fn lock(&self) -> MutexGuard<'_, HashMap<i64, (Instant, CreateAlertOptions)>> {
+        let mut forms = self.0.lock().unwrap();
+        forms.retain(|_, (created_at, _)| created_at.elapsed() < PENDING_ALERT_TTL);
+        forms
+    }
+}
+
+// Opens the guided form, used when /create_alert is sent without options.
+pub async fn open_modal(ctx: &Context, command: &ApplicationCommandInteraction) {
+    if let Err(why) = command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|modal| {
+                    modal
+                        .custom_id(MODAL_ID)
+                        .title("Create a new alert")
+                        .components(|c| form_inputs(c))
+                })
+        })
+        .await
+    {
+        println!("Cannot open modal: {}", why);
+    }
+}
+
+fn form_inputs(components: &mut CreateComponents) -> &mut CreateComponents {
+    components
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("cooler")
+                    .label("Cooler address")
+                    .placeholder("0x...")
+                    .style(InputTextStyle::Short)
+                    .min_length(42) // enforce length of EVM address.
+                    .max_length(42) // enforce length of EVM address.
+                    .required(true)
+            })
+        })
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("loan_id")
+                    .label("Loan ID (leave blank to pick an open loan)")
+                    .style(InputTextStyle::Short)
+                    .required(false)
+            })
+        })
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("threshold")
+                    .label("Threshold (e.g. 36h, 2d, 1w)")
+                    .style(InputTextStyle::Short)
+                    .required(true)
+            })
+        })
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("webhook_url")
+                    .label("Webhook URL")
+                    .placeholder("https://discord.com/api/webhooks/123/XXX")
+                    .style(InputTextStyle::Short)
+                    .required(false)
+            })
+        })
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("email")
+                    .label("Email")
+                    .placeholder("cooler_alerts@yxz.com")
+                    .style(InputTextStyle::Short)
+                    .required(false)
+            })
+        })
+}
+
+// Reads the submitted form. The loan ID can be left blank, in which case the returned options
+// have a placeholder loan ID that gets replaced once the user picks one of the open loans.
+fn read_modal(modal: &ModalSubmitInteraction) -> Result<(Option<i64>, CreateAlertOptions), OptionError> {
+    let loan_id = match modal_value(modal, "loan_id") {
+        Some(loan_id) => match loan_id.parse::<i64>() {
+            Ok(loan_id) if loan_id >= 0 => Some(loan_id),
+            _ => return Err(OptionError::Invalid { name: "loan_id", reason: format!("`{loan_id}` is not a loan ID.") }),
+        },
+        None => None,
     };
+    let threshold = parse_duration(modal_value(modal, "threshold").ok_or(OptionError::Missing("threshold"))?)
+        .map_err(|reason| OptionError::Invalid { name: "threshold", reason })?;
+
+    Ok((loan_id, CreateAlertOptions {
+        cooler: parse_address(modal_value(modal, "cooler").ok_or(OptionError::Missing("cooler"))?)
+            .map_err(|reason| OptionError::Invalid { name: "cooler", reason })?,
+        loan_id: loan_id.unwrap_or_default(),
+        threshold,
+        webhook_url: modal_value(modal, "webhook_url").map(|webhook_url| webhook_url.to_string()),
+        email: modal_value(modal, "email").map(|email| email.to_string()),
+        // Discord forms hold at most 5 inputs, all taken already. Reminders after expiry need the slash command option.
+        post_expiry: false,
+    }))
+}
 
-    match &options.get(4) {
-        Some(receiver) => {
-            match &receiver.value {
-                Some(receiver) => match receiver.as_str() {
-                    Some(receiver) => {
-                        if receiver.contains("https://") || receiver.contains("http://") {
-                            webhook_url = Some(receiver.to_string());
-                        } else if receiver.contains("@") {
-                            email = Some(receiver.to_string());
-                        } else {
-                            return CreateEmbed::default()
-                            .title("Something went wrong!")
-                            .description("Invalid webhoook or email. Please try again with a valid input format.")
-                            .color(0xDB4B4B)
-                            .to_owned();
-                        }
-                    },
-                    None => (),
-                },
-                None => (),
-            };
+// Handles the submission of the guided form.
+pub async fn handle_modal(ctx: &Context, context: &CreateContext<'_>, provider: Arc<Provider<Http>>, pending: &PendingAlerts, modal: &ModalSubmitInteraction, ephemeral: bool) {
+    let user_id = modal.user.id.0 as i64;
+    let (loan_id, options) = match read_modal(modal) {
+        Ok(form) => form,
+        Err(error) => return respond_to_modal(ctx, modal, error.embed(), ephemeral).await,
+    };
+    if options.webhook_url.is_none() && options.email.is_none() {
+        return respond_to_modal(ctx, modal, missing_receiver_embed(), ephemeral).await;
+    }
+
+    // Checking the webhook or reading the loans one by one can take longer than discord waits for a response.
+    // The loan picker is always private, since the pending form is tied to the user who submitted it.
+    if let Err(why) = modal
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|message| message.ephemeral(ephemeral || loan_id.is_none()))
+        })
+        .await
+    {
+        println!("Cannot respond to modal: {}", why);
+        return;
+    }
+
+    let (embed, components) = match loan_id {
+        Some(_) => {
+            let embed = create(context, user_id, modal.guild_id.map(|guild_id| guild_id.0 as i64), EventSource::Command, options).await;
+            (embed, CreateComponents::default())
         },
-        None => ()
+        // Without a loan ID, list the open loans of the Cooler so that the user can pick one.
+        None => loan_picker(provider, pending, user_id, options).await,
     };
 
-    let [email_check, webhook_check] = match create_alert(database, user_id, cooler, loan_id, threshold, webhook_url, email).await {
-        Ok([email_check, webhook_check]) => [email_check, webhook_check],
-        Err(error) => {
-            return CreateEmbed::default()
+    if let Err(why) = modal
+        .edit_original_interaction_response(&ctx.http, |response| response.set_embed(embed).set_components(components))
+        .await
+    {
+        println!("Cannot respond to modal: {}", why);
+    }
+}
+
+// Menu with the open loans of the Cooler. The form is kept until the user picks one of them.
+async fn loan_picker(provider: Arc<Provider<Http>>, pending: &PendingAlerts, user_id: i64, options: CreateAlertOptions) -> (CreateEmbed, CreateComponents) {
+    let cooler = format_address(&options.cooler);
+    match get_open_loans(provider, options.cooler).await {
+        Ok(loans) if loans.is_empty() => (CreateEmbed::default()
+            .title("No open loans")
+            .description(format!("The Cooler `{cooler}` doesn't have any loan waiting to be repaid."))
+            .color(0xDB4B4B)
+            .to_owned(), CreateComponents::default()),
+        Ok(loans) => {
+            let mut components = CreateComponents::default();
+            components.create_action_row(|row| {
+                row.create_select_menu(|menu| {
+                    menu.custom_id(LOAN_SELECT_ID)
+                        .placeholder("Pick a loan")
+                        .options(|menu_options| {
+                            for (loan_id, loan) in loans.iter().take(MAX_LOAN_CHOICES) {
+                                menu_options.create_option(|option| {
+                                    let expiry = match loan.status {
+                                        LoanStatus::Expired => format!("Expired on {}", format_timestamp(loan.expiry)),
+                                        _ => format!("Expires on {}", format_timestamp(loan.expiry)),
+                                    };
+                                    option.label(format!("Loan {loan_id}")).value(loan_id).description(expiry)
+                                });
+                            }
+                            menu_options
+                        })
+                })
+            });
+            let embed = CreateEmbed::default()
+                .title("Which loan should be monitored?")
+                .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
+                .color(0xC7D5E8)
+                .to_owned();
+            pending.insert(user_id, options);
+            (embed, components)
+        },
+        Err(error) => (CreateEmbed::default()
             .title("Something went wrong!")
-            .description("Error when trying to register the alerts in the database. Please try again.")
+            .description("Unable to read the loans from the Cooler contract. Please check the cooler address.")
             .field("Error", error.to_string(), false)
             .color(0xDB4B4B)
-            .to_owned();
-        }
-    };
+            .to_owned(), CreateComponents::default()),
+    }
+}
 
-    let alerts_after = match count_user_alerts(database, user_id).await {
-        Ok(alerts) => alerts,
-        Err(error) => {
-            return CreateEmbed::default()
-            .title("Something went wrong!")
-            .description("Unable to retrieve current alerts form the DB. Please try again.")
-            .field("Error", error.to_string(), false)
+// Handles the loan picked from the open loans of the Cooler, completing the guided form.
+pub async fn handle_loan_select(ctx: &Context, context: &CreateContext<'_>, pending: &PendingAlerts, component: &MessageComponentInteraction) {
+    let user_id = component.user.id.0 as i64;
+    let loan_id = component.data.values.first().and_then(|loan_id| loan_id.parse::<i64>().ok());
+    let options = pending.take(user_id);
+
+    // Checking the webhook can take longer than discord waits for a response, so the menu is updated afterwards.
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| response.kind(InteractionResponseType::DeferredUpdateMessage))
+        .await
+    {
+        println!("Cannot respond to select menu: {}", why);
+        return;
+    }
+
+    let embed = match (loan_id, options) {
+        (Some(loan_id), Some(options)) => {
+            let guild_id = component.guild_id.map(|guild_id| guild_id.0 as i64);
+            create(context, user_id, guild_id, EventSource::Component, CreateAlertOptions { loan_id, ..options }).await
+        },
+        _ => CreateEmbed::default()
+            .title("This form has expired")
+            .description("Please try again by using the slash command /create_alert.")
             .color(0xDB4B4B)
-            .to_owned();
-        }
+            .to_owned(),
     };
 
-    if alerts_after == alerts_prev {
-        return CreateEmbed::default()
-            .title("Something went wrong!")
-            .description("Error when trying to register the alerts in the database. Please try again.")
-            .color(0xDB4B4B)
-            .to_owned();
+    if let Err(why) = component
+        .edit_original_interaction_response(&ctx.http, |response| response.set_embed(embed).set_components(CreateComponents::default()))
+        .await
+    {
+        println!("Cannot respond to select menu: {}", why);
     }
+}
 
-    // Return the success embed.
-    CreateEmbed::default()
-        .title("Alert successfully added")
-        .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
-        .field("Loan ID", loan_id, true)
-        .field("", "", true)
-        .field("Alert threshold", format!("{threshold} days"), true)
-        .field("Webhook Notification?", webhook_check, true)
-        .field("", "", true)
-        .field("Email Notification?", email_check, true)
-        .field("", "", false)
-        .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
-        .color(0x2AC3DE)
-        .to_owned()
-}
\ No newline at end of file
+async fn respond_to_modal(ctx: &Context, modal: &ModalSubmitInteraction, embed: CreateEmbed, ephemeral: bool) {
+    if let Err(why) = modal
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.add_embed(embed).ephemeral(ephemeral))
+        })
+        .await
+    {
+        println!("Cannot respond to modal: {}", why);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::options::tests::option;
+    use serenity::json::json;
+
+    #[test]
+    fn parses_options_by_name() {
+        // Discord only sends the options filled by the user, so `email` may come right after `threshold`.
+        let raw = vec![
+            option("cooler", 3, json!("0x0000000000000000000000000000000000000001")),
+            option("loan_id", 4, json!(2)),
+            option("threshold", 3, json!("36h")),
+            option("email", 3, json!("cooler_alerts@yxz.com")),
+        ];
+        assert_eq!(CreateAlertOptions::from_options(&raw), Ok(CreateAlertOptions {
+            cooler: Address::from_low_u64_be(1),
+            loan_id: 2,
+            threshold: 36 * 3600,
+            webhook_url: None,
+            email: Some("cooler_alerts@yxz.com".to_string()),
+            post_expiry: false,
+        }));
+    }
+
+    #[test]
+    fn rejects_missing_and_invalid_options() {
+        let raw = vec![
+            option("cooler", 3, json!("0x0000000000000000000000000000000000000001")),
+            option("threshold", 3, json!("2d")),
+        ];
+        assert_eq!(CreateAlertOptions::from_options(&raw), Err(OptionError::Missing("loan_id")));
+
+        let raw = vec![
+            option("cooler", 3, json!("0x0000000000000000000000000000000000000001")),
+            option("loan_id", 4, json!(0)),
+            option("threshold", 3, json!("2 fortnights")),
+        ];
+        assert!(matches!(CreateAlertOptions::from_options(&raw), Err(OptionError::Invalid { name: "threshold", .. })));
+    }
+}
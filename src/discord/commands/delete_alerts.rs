@@ -1,4 +1,3 @@
-use sqlx;
 use serenity::builder::{
     CreateApplicationCommand,
     CreateEmbed,
@@ -7,82 +6,73 @@ use serenity::model::prelude::{
     command::CommandOptionType,
     application_command::CommandDataOption,
 };
-use crate::registry::utils::{
-    count_user_alerts_by_cooler,
-    delete_user_alerts_by_cooler
-};
+use ethers::types::Address;
+use crate::address::format_address;
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::error::RegistryError;
+use crate::registry::Registry;
+use crate::registry::retention::Retention;
+use crate::registry::types::Actor;
 
-pub async fn run(database: &sqlx::SqlitePool, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {    
-    let cooler = match &options.get(0).expect("Expected cooler").value {
-        Some(input) => match input.as_str() {
-            Some(cooler_address) => cooler_address,
-            None => panic!("Expected cooler"),
-        },
-        None => panic!("Expected cooler"),    
-    };
+// Options of the /delete_alerts command.
+#[derive(Debug, PartialEq)]
+pub struct DeleteAlertsOptions {
+    pub cooler: Address,
+    // When missing, all the alerts of the cooler are deleted.
+    pub loan_id: Option<i64>,
+}
 
-    let deleted_loan_id: Option<i64> = match &options.get(1) {
-        Some(input) => {
-            match &input.value {
-                Some(loan_id) => loan_id.as_i64(),
-                None => panic!("Expected loan_id"),
-            }
-        },
-        None => None
-    };
+impl FromOptions for DeleteAlertsOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self {
+            cooler: options.required_address("cooler")?,
+            loan_id: options.i64("loan_id")?,
+        })
+    }
+}
 
-    let alerts = match count_user_alerts_by_cooler(database, user_id, cooler, deleted_loan_id).await {
-        Ok(alerts) => alerts,
-        Err(error) => {
-            return CreateEmbed::default()
-            .title("Something went wrong!")
-            .description("Unable to retrieve current alerts form the DB. Please try again.")
-            .field("Error", error.to_string(), false)
-            .color(0xDB4B4B)
-            .to_owned();
-        }
+pub async fn run(registry: &Registry, retention: Retention, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {    
+    let DeleteAlertsOptions { cooler, loan_id: deleted_loan_id } = match DeleteAlertsOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
     };
 
-    if alerts == 0 {
-        return CreateEmbed::default()
-        .title("No alerts found")
-        .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
-        .field("", "", false)
-        .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
-        .color(0xDB4B4B)
-        .to_owned();
-    }
-
-    match delete_user_alerts_by_cooler(database, user_id, cooler, deleted_loan_id).await {
-        Ok(alerts) => alerts,
-        Err(error) => {
+    let deleted = registry.delete_user_alerts_by_cooler(user_id, cooler, deleted_loan_id, Actor::command(user_id)).await;
+    let cooler = format_address(&cooler);
+    match deleted {
+        Ok(_) => (),
+        Err(RegistryError::NotFound) => {
             return CreateEmbed::default()
-            .title("Something went wrong!")
-            .description("Unable to delete alerts form the DB. Please try again.")
-            .field("Error", error.to_string(), false)
+            .title("No alerts found")
+            .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
+            .field("", "", false)
+            .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
             .color(0xDB4B4B)
             .to_owned();
-        }
+        },
+        Err(error) => return registry_error_embed(&error, "Unable to delete alerts form the DB. Please try again."),
     }
     match deleted_loan_id {
         Some(loan_id) => {
-            return CreateEmbed::default()
+            CreateEmbed::default()
             .title("Alerts successfully deleted")
             .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
             .field("Loan ID", loan_id, false)
-            .field("", "", false)
+            .field("", format!("You can restore them with /restore_alerts within {} days.", retention.days), false)
             .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
             .color(0x2AC3DE)
-            .to_owned();
+            .to_owned()
         },
         None => {
-            return CreateEmbed::default()
+            CreateEmbed::default()
             .title("Alerts successfully deleted")
             .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
-            .field("", "", false)
+            .field("", format!("You can restore them with /restore_alerts within {} days.", retention.days), false)
             .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
             .color(0x2AC3DE)
-            .to_owned();
+            .to_owned()
         }
     }
 }
@@ -95,6 +85,7 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 .description("The address of the Cooler contract. Must starts with `0x`.")
                 .kind(CommandOptionType::String)
                 .required(true)
+                .set_autocomplete(true)
                 .min_length(42) // enforce length of EVM address.
                 .max_length(42) // enforce length of EVM address.
         })
@@ -104,5 +95,6 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 .description("If no ID is informed, all alerts for the given Cooler will be deleted.")
                 .kind(CommandOptionType::Integer)
                 .required(false)
+                .set_autocomplete(true)
         })
 }
\ No newline at end of file
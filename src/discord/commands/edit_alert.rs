@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use ethers::providers::{Http, Provider};
+use crate::address::format_address;
+use crate::duration::parse_duration;
+use crate::discord::commands::list_alerts::format_threshold;
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::discord::validation::{receivers_error_embed, validate_receivers};
+use crate::listener::loans::{get_loan, LoanStatus};
+use crate::registry::crypto::FieldCipher;
+use crate::registry::Registry;
+use crate::registry::types::{Actor, AlertUpdate, EventSource};
+
+// Options of the /edit_alert command.
+#[derive(Debug, PartialEq)]
+pub struct EditAlertOptions {
+    pub alert_id: i64,
+    // Threshold in seconds.
+    pub threshold: Option<i64>,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+}
+
+impl FromOptions for EditAlertOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        let threshold = match options.str("threshold")? {
+            Some(threshold) => Some(parse_duration(threshold).map_err(|reason| OptionError::Invalid { name: "threshold", reason })?),
+            None => None,
+        };
+
+        Ok(Self {
+            alert_id: options.required_i64("alert_id")?,
+            threshold,
+            webhook_url: options.str("webhook_url")?.map(|webhook_url| webhook_url.to_string()),
+            email: options.str("email")?.map(|email| email.to_string()),
+        })
+    }
+}
+
+pub async fn run(registry: &Registry, cipher: &FieldCipher, provider: Arc<Provider<Http>>, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
+    match EditAlertOptions::from_options(options) {
+        Ok(options) => edit(registry, cipher, provider, user_id, EventSource::Command, options).await,
+        Err(error) => error.embed(),
+    }
+}
+
+// Applies the changes to the alert, shared by the slash command and the "Edit threshold" button.
+pub async fn edit(registry: &Registry, cipher: &FieldCipher, provider: Arc<Provider<Http>>, user_id: i64, source: EventSource, options: EditAlertOptions) -> CreateEmbed {
+    let EditAlertOptions { alert_id, threshold, webhook_url, email } = options;
+    if threshold.is_none() && webhook_url.is_none() && email.is_none() {
+        return CreateEmbed::default()
+        .title("Nothing to update!")
+        .description("Please try again informing a new threshold, webhook URL or email.")
+        .color(0xDB4B4B)
+        .to_owned();
+    }
+
+    let alert = match registry.get_user_alert(user_id, alert_id).await {
+        // Users can only edit their own alerts.
+        Ok(alert) => alert,
+        Err(error) => return registry_error_embed(&error, "Unable to retrieve the alert form the DB. Please try again."),
+    };
+
+    let (webhook_url, email) = match validate_receivers(webhook_url.as_deref(), email.as_deref()).await {
+        Ok(receivers) => receivers,
+        Err(errors) => return receivers_error_embed(errors),
+    };
+
+    let check = |enabled: bool| match enabled {
+        true => ":white_check_mark:",
+        false => ":x:",
+    };
+
+    // Re-arm the alert if it already triggered and the loan is still further from expiry than the new threshold.
+    let armed = alert.is_active() && alert.get_stage() == 0;
+    let mut rearm = false;
+    if let (Some(threshold), false) = (threshold, armed) {
+        match get_loan(provider, alert.get_cooler(), alert.get_loan_id()).await {
+            Ok(loan) => rearm = loan.status == LoanStatus::Active && loan.time_left() > threshold as u64,
+            Err(error) => println!("Unable to read loan for alert {alert_id}: {:?}", error),
+        }
+    }
+
+    let (webhook_check, email_check) = (
+        check(webhook_url.is_some() || alert.get_encrypted_webhook_url().is_some()),
+        check(email.is_some() || alert.get_encrypted_email().is_some()),
+    );
+    let update = AlertUpdate {
+        threshold,
+        webhook_url: webhook_url.map(|webhook_url| cipher.seal_webhook_url(&webhook_url)),
+        email: email.map(|email| cipher.seal_email(&email)),
+        rearm,
+    };
+    match registry.update_alert(user_id, alert_id, update, Actor { user_id: Some(user_id), source }).await {
+        Ok(_) => (),
+        Err(error) => return registry_error_embed(&error, "Error when trying to update the alert in the database. Please try again."),
+    }
+
+    let active = rearm || alert.is_active();
+    CreateEmbed::default()
+        .title("Alert successfully updated")
+        .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})", cooler = format_address(&alert.get_cooler())))
+        .field("Alert ID", alert_id, true)
+        .field("Loan ID", alert.get_loan_id(), true)
+        .field("Alert threshold", format_threshold(threshold.unwrap_or(alert.get_threshold()), alert.has_post_expiry()), true)
+        .field("Webhook Notification?", webhook_check, true)
+        .field("Email Notification?", email_check, true)
+        .field("Already Triggered?", check(!active), true)
+        .footer(|f| f.text(match rearm {
+            true => "The alert has been re-armed and will trigger again once the new threshold is crossed.",
+            false => "Remember that you can check your current alerts by using the slash command /list_alerts.",
+        }))
+        .color(0x2AC3DE)
+        .to_owned()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("edit_alert").description("Change the threshold or the receivers of an alert")
+        .create_option(|option| {
+            option
+                .name("alert_id")
+                .description("The ID of the alert to be edited. You can find it by using /list_alerts.")
+                .kind(CommandOptionType::Integer)
+                .required(true)
+                .min_int_value(0)
+        })
+        .create_option(|option| {
+            option
+                .name("threshold")
+                .description("How long before expiration the user should be notified. Examples: 36h, 2d, 1w.")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("webhook_url")
+                .description("The webhook URL where the alerts should be sent. Example: https://discord.com/api/webhooks/123/XXX")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("email")
+                .description("The email address where the alerts should be sent. Example: cooler_alerts@yxz.com")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+}
@@ -0,0 +1,64 @@
+use std::borrow::Cow;
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::channel::AttachmentType;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use crate::discord::alert_file::write_alerts;
+use crate::discord::errors::registry_error_embed;
+use crate::registry::crypto::FieldCipher;
+use crate::registry::types::AlertFilter;
+use crate::registry::Registry;
+
+const FILE_NAME: &str = "cooler-alerts.csv";
+
+// Sends the alerts of the user as a CSV file in a direct message, so that the file isn't left in a server channel.
+pub async fn run(ctx: &Context, registry: &Registry, cipher: &FieldCipher, user: &User) -> CreateEmbed {
+    let user_id = user.id.0 as i64;
+    let alerts = match registry.get_user_alerts(user_id, &AlertFilter::default(), i64::MAX, 0).await {
+        Ok(alerts) if alerts.is_empty() => {
+            return CreateEmbed::default()
+            .title("No alerts found")
+            .description("You don't have any alert to export.")
+            .footer(|f| f.text("Remember that you can create alerts by using the slash command /create_alert."))
+            .color(0xDB4B4B)
+            .to_owned();
+        },
+        Ok(alerts) => alerts,
+        Err(error) => return registry_error_embed(&error, "Unable to read your alerts from the DB. Please try again."),
+    };
+
+    let file = AttachmentType::Bytes { data: Cow::Owned(write_alerts(&alerts, cipher).into_bytes()), filename: FILE_NAME.to_string() };
+    let sent = user
+        .direct_message(ctx, |message| {
+            message
+                .content(format!(
+                    "Here are your {} alerts. Webhook URLs and emails are encrypted, and can only be read by this bot. \
+                    Use /import_alerts with this file to bring them to another account or server.",
+                    alerts.len(),
+                ))
+                .add_file(file)
+        })
+        .await;
+    match sent {
+        Ok(_) => CreateEmbed::default()
+            .title("Alerts exported")
+            .description(format!("{} alerts have been sent to you in a direct message.", alerts.len()))
+            .color(0x2AC3DE)
+            .to_owned(),
+        Err(why) => {
+            println!("Cannot send the exported alerts: {}", why);
+            CreateEmbed::default()
+            .title("Unable to send you a direct message")
+            .description("Please allow direct messages from the members of this server, then try again.")
+            .color(0xDB4B4B)
+            .to_owned()
+        },
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("export_alerts").description("Receive a file with all your alerts in a direct message.")
+}
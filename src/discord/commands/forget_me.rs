@@ -0,0 +1,78 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateComponents,
+    CreateEmbed,
+};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::{
+    message_component::MessageComponentInteraction,
+    InteractionResponseType,
+};
+use serenity::prelude::*;
+use crate::discord::Reply;
+use crate::discord::commands::create_alert::PendingAlerts;
+use crate::discord::errors::registry_error_embed;
+use crate::registry::Registry;
+
+// Custom IDs of the confirmation buttons. The reply is private, and the data of whoever clicks is the one erased.
+pub const CONFIRM_ID: &str = "forget_me:confirm";
+pub const CANCEL_ID: &str = "forget_me:cancel";
+
+// Asks for a confirmation before erasing anything, as the deletion can't be undone.
+pub fn run() -> Reply {
+    let embed = CreateEmbed::default()
+        .title("Erase all your data?")
+        .description(
+            "All your alerts, including the deleted ones that could still be restored, and their whole history will be erased for good. \
+            You will stop receiving notifications right away.",
+        )
+        .footer(|f| f.text("You can keep a copy of your alerts with /export_alerts before erasing them."))
+        .color(0xDB4B4B)
+        .to_owned();
+    let components = CreateComponents::default()
+        .create_action_row(|row| {
+            row.create_button(|b| b.custom_id(CONFIRM_ID).label("Erase everything").style(ButtonStyle::Danger))
+                .create_button(|b| b.custom_id(CANCEL_ID).label("Cancel").style(ButtonStyle::Secondary))
+        })
+        .to_owned();
+    Reply { embed, components: Some(components) }
+}
+
+// Handles a click on one of the confirmation buttons, replacing the question with the outcome.
+pub async fn handle_button(ctx: &Context, registry: &Registry, pending: &PendingAlerts, component: &MessageComponentInteraction) {
+    let user_id = component.user.id.0 as i64;
+    let embed = match component.data.custom_id.as_str() {
+        CONFIRM_ID => {
+            // The unfinished form holds receivers too, and could still create an alert.
+            pending.take(user_id);
+            match registry.forget_user(user_id).await {
+                Ok(forgotten) => CreateEmbed::default()
+                    .title("Your data has been erased")
+                    .description(format!("{} alerts and {} history entries have been deleted.", forgotten.alerts, forgotten.events))
+                    .color(0x2AC3DE)
+                    .to_owned(),
+                Err(error) => registry_error_embed(&error, "Unable to erase your data. Please try again."),
+            }
+        },
+        _ => CreateEmbed::default()
+            .title("Nothing was erased")
+            .description("Your alerts are still being monitored.")
+            .color(0xC7D5E8)
+            .to_owned(),
+    };
+
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|message| message.set_embed(embed).set_components(CreateComponents::default()))
+        })
+        .await
+    {
+        println!("Cannot respond to button: {}", why);
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("forget_me").description("Erase all your alerts and everything the bot stores about you.")
+}
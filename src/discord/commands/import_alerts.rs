@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandData,
+};
+use crate::discord::DEFERRED_TIMEOUT;
+use crate::discord::alert_file::{read_alerts, ImportedAlert, HEADER, MAX_FILE_SIZE, MAX_ROWS};
+use crate::discord::commands::create_alert::CreateContext;
+use crate::discord::limits::cooldown_embed;
+use crate::discord::options::{OptionError, Options};
+use crate::discord::validation::{validate_email, validate_webhook_url};
+use crate::registry::types::{Actor, CreatedAlert, NewAlert};
+
+// Room kept in the embed description for the line telling how many results were left out.
+const DESCRIPTION_SIZE: usize = 3900;
+
+// Time kept before the command times out to answer with the rows imported so far.
+const REPLY_MARGIN: Duration = Duration::from_secs(10);
+
+// Reported for the rows left when the time is up.
+const TIMED_OUT: &str = "Not imported in time, please import this line again.";
+
+// Outcome of the validation of each webhook URL found in the file, as several alerts often share one.
+type CheckedWebhooks = HashMap<String, Result<String, String>>;
+
+pub async fn run(context: &CreateContext<'_>, user_id: i64, guild_id: Option<i64>, data: &CommandData) -> CreateEmbed {
+    let CreateContext { limits, .. } = context;
+    let attachment = match Options::new(&data.options).str("file") {
+        Ok(Some(attachment_id)) => data.resolved.attachments.values().find(|attachment| attachment.id.to_string() == attachment_id),
+        Ok(None) => None,
+        Err(error) => return error.embed(),
+    };
+    let Some(attachment) = attachment else {
+        return OptionError::Missing("file").embed();
+    };
+    if attachment.size > MAX_FILE_SIZE {
+        return invalid_file_embed(format!("The file is too large, the limit is {} KB.", MAX_FILE_SIZE / 1024));
+    }
+    // The whole file counts as a single creation, so that it isn't rejected row after row.
+    if let Err(remaining) = limits.cooldown.check(user_id) {
+        return cooldown_embed(remaining);
+    }
+
+    let file = match attachment.download().await.map(String::from_utf8) {
+        Ok(Ok(file)) => file,
+        Ok(Err(_)) => return invalid_file_embed("The file must be a CSV text file, as sent by /export_alerts.".to_string()),
+        Err(why) => {
+            println!("Cannot download the imported file: {}", why);
+            return invalid_file_embed("The file couldn't be downloaded. Please try again.".to_string());
+        },
+    };
+    let rows = match read_alerts(&file) {
+        Ok(rows) if rows.is_empty() => return invalid_file_embed("The file doesn't contain any alert.".to_string()),
+        Ok(rows) if rows.len() > MAX_ROWS => return invalid_file_embed(format!("The file has {} alerts, the limit is {MAX_ROWS} per file.", rows.len())),
+        Ok(rows) => rows,
+        Err(error) => return invalid_file_embed(error),
+    };
+
+    // Stop before the command times out, so that the rows already imported are still reported.
+    let deadline = Instant::now() + DEFERRED_TIMEOUT - REPLY_MARGIN;
+    let mut webhooks = CheckedWebhooks::new();
+    let (mut created, mut updated, mut failed) = (0, 0, 0);
+    let mut results: Vec<String> = Vec::new();
+    let mut timed_out = false;
+    for (line, row) in rows {
+        let result = match row {
+            // Once out of time, the remaining rows are still reported, invalid ones included.
+            Ok(_) if timed_out => Err(TIMED_OUT.to_string()),
+            Ok(alert) => match timeout_at(deadline, import(context, user_id, guild_id, alert, &mut webhooks)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    timed_out = true;
+                    Err(TIMED_OUT.to_string())
+                },
+            },
+            Err(error) => Err(error),
+        };
+        results.push(match result {
+            Ok(CreatedAlert::New(alert_id)) => {
+                created += 1;
+                format!(":white_check_mark: Line {line}: created alert #{alert_id}.")
+            },
+            Ok(CreatedAlert::Updated(alert_id)) => {
+                updated += 1;
+                format!(":white_check_mark: Line {line}: updated the threshold of alert #{alert_id}.")
+            },
+            Err(error) => {
+                failed += 1;
+                format!(":x: Line {line}: {error}")
+            },
+        });
+    }
+
+    if created + updated > 0 {
+        limits.cooldown.record(user_id);
+    }
+
+    CreateEmbed::default()
+        .title("Alerts imported")
+        .description(summarize(&results))
+        .field("Created", created, true)
+        .field("Updated", updated, true)
+        .field("Failed", failed, true)
+        .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+        .color(if failed == 0 { 0x2AC3DE } else { 0xDB4B4B })
+        .to_owned()
+}
+
+// Validates the receivers of a row the same way /create_alert does, then registers the alert.
+async fn import(context: &CreateContext<'_>, user_id: i64, guild_id: Option<i64>, alert: ImportedAlert, webhooks: &mut CheckedWebhooks) -> Result<CreatedAlert, String> {
+    let CreateContext { registry, cipher, limits } = context;
+    let ImportedAlert { cooler, loan_id, threshold, post_expiry, webhook_url, email } = alert;
+    if webhook_url.is_none() && email.is_none() {
+        return Err("Missing alert receiver, please inform a webhook URL or an email.".to_string());
+    }
+
+    // Exported receivers are encrypted, while those written by hand are read as they are.
+    let open = |receiver: Option<String>| receiver.map(|receiver| cipher.open(&receiver)).transpose().map_err(|error| error.to_string());
+    let (webhook_url, email) = (open(webhook_url)?, open(email)?);
+    let webhook_url = match webhook_url {
+        Some(webhook_url) => Some(match webhooks.get(&webhook_url) {
+            Some(checked) => checked.clone(),
+            None => {
+                let checked = validate_webhook_url(&webhook_url).await.map_err(|error| error.message);
+                webhooks.insert(webhook_url, checked.clone());
+                checked
+            },
+        }),
+        None => None,
+    };
+    let email = email.map(|email| validate_email(&email).map_err(|error| error.message));
+    let (webhook_url, email) = match (webhook_url.transpose(), email.transpose()) {
+        (Ok(webhook_url), Ok(email)) => (webhook_url, email),
+        (Err(error), Ok(_)) | (Ok(_), Err(error)) => return Err(error),
+        (Err(webhook_error), Err(email_error)) => return Err(format!("{webhook_error} {email_error}")),
+    };
+
+    let webhook_url = webhook_url.map(|webhook_url| cipher.seal_webhook_url(&webhook_url));
+    let email = email.map(|email| cipher.seal_email(&email));
+    let alert = NewAlert { user_id, cooler, loan_id, threshold, post_expiry, webhook_url, email, guild_id };
+    registry.create_alert(alert, &limits.quota, Actor::command(user_id)).await.map_err(|error| error.to_string())
+}
+
+// Joins the results, leaving out those that don't fit in the embed.
+fn summarize(results: &[String]) -> String {
+    let mut summary = String::new();
+    for (index, result) in results.iter().enumerate() {
+        if summary.len() + result.len() > DESCRIPTION_SIZE {
+            summary.push_str(&format!("…and {} more lines.", results.len() - index));
+            break;
+        }
+        summary.push_str(result);
+        summary.push('\n');
+    }
+    summary
+}
+
+fn invalid_file_embed(reason: String) -> CreateEmbed {
+    CreateEmbed::default()
+        .title("Invalid file!")
+        .description(reason)
+        .footer(|f| f.text(format!("Use a file sent by /export_alerts, or a CSV file with the columns {HEADER}.")))
+        .color(0xDB4B4B)
+        .to_owned()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("import_alerts").description("Create the alerts of a file sent by /export_alerts.")
+        .create_option(|option| {
+            option
+                .name("file")
+                .description("CSV file with one alert per line, as sent by /export_alerts.")
+                .kind(CommandOptionType::Attachment)
+                .required(true)
+        })
+}
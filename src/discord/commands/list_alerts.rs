@@ -1,67 +1,198 @@
-use sqlx;
 use serenity::builder::{
     CreateApplicationCommand,
+    CreateComponents,
     CreateEmbed,
 };
-use crate::registry::utils::get_user_alerts;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::address::{canonical_address, format_address};
+use crate::discord::Reply;
+use crate::discord::components::alert_buttons;
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::duration::format_duration;
+use crate::registry::types::{AlertDB, AlertFilter, PAUSED_INDEFINITELY};
+use crate::registry::Registry;
+
+// Alerts shown per page. Each of them gets a row of buttons, and discord allows 5 rows per message
+// so the last one is kept for the page navigation.
+const PAGE_SIZE: i64 = 4;
+
+// Options of the /list_alerts command.
+#[derive(Debug, Default, PartialEq)]
+pub struct ListAlertsOptions {
+    pub filter: AlertFilter,
+}
 
+impl FromOptions for ListAlertsOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        let active = match options.str("state")? {
+            Some("active") => Some(true),
+            Some("triggered") => Some(false),
+            Some(_) | None => None,
+        };
+        Ok(Self { filter: AlertFilter { cooler: options.address("cooler")?, active } })
+    }
+}
 
-pub async fn run(database: &sqlx::SqlitePool, user_id: i64) -> CreateEmbed {
-    let alerts = match get_user_alerts(database, user_id).await {
+pub async fn run(registry: &Registry, user_id: i64, options: &[CommandDataOption]) -> Reply {
+    match ListAlertsOptions::from_options(options) {
+        Ok(options) => page(registry, user_id, &options.filter, 0).await,
+        Err(error) => error.embed().into(),
+    }
+}
+
+// Builds one page of the alert list, shared by the slash command and the navigation buttons.
+pub async fn page(registry: &Registry, user_id: i64, filter: &AlertFilter, page: i64) -> Reply {
+    let num_alerts = match registry.count_filtered_user_alerts(user_id, filter).await {
+        Ok(count) => count,
+        Err(error) => return registry_error_embed(&error, "Unable to retrieve alerts form the DB. Please try again.").into(),
+    };
+    let num_pages = ((num_alerts + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
+    let page = page.clamp(0, num_pages - 1);
+
+    let alerts = match registry.get_user_alerts(user_id, filter, PAGE_SIZE, page * PAGE_SIZE).await {
         Ok(alerts) => alerts,
-        Err(error) => {
-            return CreateEmbed::default()
-            .title("Something went wrong!")
-            .description("Unable to retrieve alerts form the DB. Please try again.")
-            .field("Error", error.to_string(), false)
-            .color(0xDB4B4B)
-            .to_owned();
-        }
-    };        
+        Err(error) => return registry_error_embed(&error, "Unable to retrieve alerts form the DB. Please try again.").into(),
+    };
 
     let mut embed = CreateEmbed::default()
         .color(0xC7D5E8)
-        .field(" ", " ", false)
         .to_owned();
 
-
-    let num_alerts = alerts.len();
+    let filtered = filter != &AlertFilter::default();
     if num_alerts == 0 {
-        embed.title("You don't have any alerts");
+        match filtered {
+            true => embed.title("No alerts match the filters"),
+            false => embed.title("You don't have any alerts"),
+        };
         embed.description("You can create a new alert by using the slash command /create_alert.");
-    } else {
-        if num_alerts == 1 {
-            embed.title(format!("You have 1 alert:\n"));
-        } else {
-            embed.title(format!("You have {} alerts:\n", num_alerts));
-        }
-        for (_, alert) in alerts.iter().enumerate() {
-            let email_check = match alert.get_email() {
-                Some(_) => ":white_check_mark:",
-                None => ":x:",
-            };
-            let webhook_check = match alert.get_webhook_url() {
-                Some(_) => ":white_check_mark:",
-                None => ":x:",
-            };
-            let trigger_check = match alert.is_active() {
-                true => ":x:",
-                false => ":white_check_mark:",
-            };
-            embed.field(" ", " ", false);
-            embed.field(" ", " ", false);
-            embed.field("Cooler Contract", alert.get_cooler(), true);
-            embed.field("Loan ID", alert.get_loan_id(), true);
-            embed.field("Alert threshold", format!("{} days", alert.get_threshold()), true);
-            embed.field("Webhook Notification?", webhook_check, true);
-            embed.field("Email Notification?", email_check, true);
-            embed.field("Already Triggered?", trigger_check, true);
-        }
+        return embed.into();
+    }
+
+    match num_alerts {
+        1 => embed.title("You have 1 alert:"),
+        _ => embed.title(format!("You have {} alerts:", num_alerts)),
+    };
+    let lines: Vec<String> = alerts.iter().map(alert_line).collect();
+    embed.description(lines.join("\n"));
+    embed.footer(|f| f.text(format!("Page {} of {}", page + 1, num_pages)));
+
+    let mut components = CreateComponents::default();
+    for alert in alerts.iter() {
+        components.add_action_row(alert_buttons(alert.get_alert_id()));
+    }
+    if num_pages > 1 {
+        components.create_action_row(|row| {
+            row.create_button(|b| {
+                b.custom_id(page_custom_id(user_id, filter, page - 1))
+                    .label("Previous")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0)
+            })
+            .create_button(|b| {
+                b.custom_id(page_custom_id(user_id, filter, page + 1))
+                    .label("Next")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page + 1 >= num_pages)
+            })
+        });
     }
 
-    return embed;
+    Reply { embed, components: Some(components) }
+}
+
+// One-line summary of an alert.
+fn alert_line(alert: &AlertDB) -> String {
+    let cooler = format_address(&alert.get_cooler());
+    let short_cooler = format!("{}…{}", &cooler[..6], &cooler[cooler.len() - 4..]);
+    // Receivers are encrypted, only their masked version is shown.
+    let mut receivers: Vec<&str> = Vec::new();
+    if alert.get_encrypted_webhook_url().is_some() {
+        receivers.push(alert.get_webhook_hint().unwrap_or("webhook"));
+    }
+    if alert.get_encrypted_email().is_some() {
+        receivers.push(alert.get_email_hint().unwrap_or("email"));
+    }
+    let state = match (alert.is_active(), alert.get_paused_until()) {
+        (_, Some(PAUSED_INDEFINITELY)) => ":pause_button: Paused".to_string(),
+        (_, Some(paused_until)) => format!(":zzz: Snoozed until <t:{paused_until}:f>"),
+        (true, None) => ":bell: Active".to_string(),
+        (false, None) => ":white_check_mark: Triggered".to_string(),
+    };
+
+    format!(
+        "**#{}** · [{short_cooler}](https://www.etherscan.io/address/{cooler}) · Loan {} · {} · {} · {state}",
+        alert.get_alert_id(),
+        alert.get_loan_id(),
+        format_threshold(alert.get_threshold(), alert.has_post_expiry()),
+        receivers.join(" + "),
+    )
+}
+
+// Custom IDs of the navigation buttons follow the `list:<user_id>:<page>:<state>:<cooler>` format,
+// so that the page can be rebuilt without keeping any state around.
+fn page_custom_id(user_id: i64, filter: &AlertFilter, page: i64) -> String {
+    let state = match filter.active {
+        Some(true) => "active",
+        Some(false) => "triggered",
+        None => "all",
+    };
+    let cooler = filter.cooler.as_ref().map(canonical_address).unwrap_or_else(|| "-".to_string());
+    format!("list:{user_id}:{page}:{state}:{cooler}")
+}
+
+// Parses the custom ID of a navigation button into the list owner, filter and page.
+pub fn parse_page_custom_id(custom_id: &str) -> Option<(i64, AlertFilter, i64)> {
+    let mut parts = custom_id.splitn(5, ':');
+    if parts.next()? != "list" {
+        return None;
+    }
+    let user_id = parts.next()?.parse().ok()?;
+    let page = parts.next()?.parse().ok()?;
+    let active = match parts.next()? {
+        "active" => Some(true),
+        "triggered" => Some(false),
+        _ => None,
+    };
+    let cooler = match parts.next()? {
+        "-" => None,
+        cooler => Some(cooler.parse().ok()?),
+    };
+    Some((user_id, AlertFilter { cooler, active }, page))
+}
+
+// Human-readable alert threshold, flagging alerts that keep reminding after expiry.
+pub fn format_threshold(threshold: i64, post_expiry: bool) -> String {
+    match post_expiry {
+        true => format!("{} + after expiry", format_duration(threshold as u64)),
+        false => format_duration(threshold as u64),
+    }
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
     command.name("list_alerts").description("List all user alerts")
-}
\ No newline at end of file
+        .create_option(|option| {
+            option
+                .name("cooler")
+                .description("Only list the alerts of this Cooler contract.")
+                .kind(CommandOptionType::String)
+                .required(false)
+                .set_autocomplete(true)
+                .min_length(42) // enforce length of EVM address.
+                .max_length(42) // enforce length of EVM address.
+        })
+        .create_option(|option| {
+            option
+                .name("state")
+                .description("Only list the alerts in this state.")
+                .kind(CommandOptionType::String)
+                .required(false)
+                .add_string_choice("Active", "active")
+                .add_string_choice("Triggered", "triggered")
+        })
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use ethers::{
+    providers::{Http, Provider},
+    types::Address,
+};
+use crate::address::format_address;
+use crate::duration::format_duration;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::listener::loans::{get_cooler_tokens, get_loan, LoanStatus};
+
+// Options of the /loan_status command.
+#[derive(Debug, PartialEq)]
+pub struct LoanStatusOptions {
+    pub cooler: Address,
+    pub loan_id: i64,
+}
+
+impl FromOptions for LoanStatusOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self {
+            cooler: options.required_address("cooler")?,
+            loan_id: options.required_i64("loan_id")?,
+        })
+    }
+}
+
+pub async fn run(provider: Arc<Provider<Http>>, options: &[CommandDataOption]) -> CreateEmbed {
+    let LoanStatusOptions { cooler, loan_id } = match LoanStatusOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    let loan = match get_loan(provider.clone(), cooler, loan_id).await {
+        Ok(loan) => loan,
+        Err(error) => {
+            return CreateEmbed::default()
+            .title("Something went wrong!")
+            .description("Unable to read the loan from the Cooler contract. Please check the cooler address and loan ID.")
+            .field("Error", error.to_string(), false)
+            .color(0xDB4B4B)
+            .to_owned();
+        }
+    };
+
+    let (collateral, debt) = match get_cooler_tokens(provider, cooler).await {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            return CreateEmbed::default()
+            .title("Something went wrong!")
+            .description("Unable to read the Cooler tokens. Please try again.")
+            .field("Error", error.to_string(), false)
+            .color(0xDB4B4B)
+            .to_owned();
+        }
+    };
+
+    // Claimed loans are deleted from the Cooler, so there is nothing else to show.
+    let cooler = format_address(&cooler);
+    if loan.status == LoanStatus::Claimed {
+        return CreateEmbed::default()
+            .title("Loan status")
+            .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
+            .field("Loan ID", loan_id, true)
+            .field("Status", loan.status, true)
+            .field("", "", false)
+            .footer(|f| f.text("This loan has been claimed by the lender or doesn't exist."))
+            .color(0xC7D5E8)
+            .to_owned();
+    }
+
+    let time_left = match loan.status {
+        LoanStatus::Active => format_duration(loan.time_left()),
+        _ => "-".to_string(),
+    };
+    let color = match loan.status {
+        LoanStatus::Expired => 0xDB4B4B,
+        _ => 0x2AC3DE,
+    };
+
+    CreateEmbed::default()
+        .title("Loan status")
+        .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})"))
+        .field("Loan ID", loan_id, true)
+        .field("Status", loan.status, true)
+        .field("", "", true)
+        .field("Expiry", format!("<t:{}:F>", loan.expiry), true)
+        .field("Time Left", time_left, true)
+        .field("", "", true)
+        .field("Principal", debt.format_amount(loan.principal), true)
+        .field("Interest Due", debt.format_amount(loan.interest_due), true)
+        .field("Collateral", collateral.format_amount(loan.collateral), true)
+        .field("Lender", format!("[{lender}](https://www.etherscan.io/address/{lender})", lender = format_address(&loan.lender)), false)
+        .footer(|f| f.text("Remember that you can get notified before expiry by using the slash command /create_alert."))
+        .color(color)
+        .to_owned()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("loan_status").description("Show the current on-chain state of a loan")
+        .create_option(|option| {
+            option
+                .name("cooler")
+                .description("The address of the Cooler contract. Must starts with `0x`.")
+                .kind(CommandOptionType::String)
+                .required(true)
+                .set_autocomplete(true)
+                .min_length(42) // enforce length of EVM address.
+                .max_length(42) // enforce length of EVM address.
+        })
+        .create_option(|option| {
+            option
+                .name("loan_id")
+                .description("The ID of the loan.")
+                .kind(CommandOptionType::Integer)
+                .required(true)
+                .set_autocomplete(true)
+                .min_int_value(0)
+        })
+}
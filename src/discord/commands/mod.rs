@@ -1,3 +1,16 @@
+pub mod alert_history;
 pub mod create_alert;
 pub mod delete_alerts;
-pub mod list_alerts;
\ No newline at end of file
+pub mod edit_alert;
+pub mod export_alerts;
+pub mod forget_me;
+pub mod import_alerts;
+pub mod list_alerts;
+pub mod loan_status;
+pub mod my_data;
+pub mod pause_alert;
+pub mod reply_visibility;
+pub mod restore_alerts;
+pub mod resume_alert;
+pub mod snooze_alert;
+pub mod test_alert;
\ No newline at end of file
@@ -0,0 +1,35 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use crate::discord::errors::registry_error_embed;
+use crate::registry::Registry;
+use crate::registry::retention::Retention;
+use crate::registry::types::UserData;
+
+pub async fn run(registry: &Registry, retention: Retention, user_id: i64) -> CreateEmbed {
+    let UserData { alerts, deleted_alerts, webhooks, emails, guilds, events } = match registry.get_user_data(user_id).await {
+        Ok(data) => data,
+        Err(error) => return registry_error_embed(&error, "Unable to read your data from the DB. Please try again."),
+    };
+
+    CreateEmbed::default()
+        .title("Your data")
+        .description(
+            "The bot stores your discord user ID with each of your alerts, along with the Cooler, loan and threshold to follow. \
+            Webhook URLs and emails are encrypted, and only decrypted to deliver notifications.",
+        )
+        .field("Alerts", alerts, true)
+        .field(format!("Deleted alerts (kept {} days)", retention.days), deleted_alerts, true)
+        .field("Servers", guilds, true)
+        .field("Webhook URLs", webhooks, true)
+        .field("Emails", emails, true)
+        .field("History entries", events, true)
+        .footer(|f| f.text("You can get a copy of your alerts with /export_alerts, or erase all your data with /forget_me."))
+        .color(0xC7D5E8)
+        .to_owned()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("my_data").description("Show a summary of everything the bot stores about you.")
+}
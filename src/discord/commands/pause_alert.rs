@@ -0,0 +1,69 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::types::{Actor, PAUSED_INDEFINITELY};
+use crate::registry::Registry;
+
+// Options of the /pause_alert command.
+#[derive(Debug, PartialEq)]
+pub struct PauseAlertOptions {
+    pub alert_id: i64,
+}
+
+impl FromOptions for PauseAlertOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self { alert_id: options.required_i64("alert_id")? })
+    }
+}
+
+pub async fn run(registry: &Registry, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
+    let PauseAlertOptions { alert_id } = match PauseAlertOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    set_paused(registry, user_id, alert_id, true).await
+}
+
+// Pauses an alert until it is resumed, or resumes it, shared by /pause_alert and /resume_alert.
+pub async fn set_paused(registry: &Registry, user_id: i64, alert_id: i64, paused: bool) -> CreateEmbed {
+    let paused_until = paused.then_some(PAUSED_INDEFINITELY);
+    match (registry.set_alert_paused_until(user_id, alert_id, paused_until, Actor::command(user_id)).await, paused) {
+        (Ok(_), true) => CreateEmbed::default()
+            .title("Alert paused")
+            .description(format!("Alert #{alert_id} won't send any notification until you resume it."))
+            .field("", "", false)
+            .footer(|f| f.text("You can resume it at any time by using the slash command /resume_alert."))
+            .color(0x2AC3DE)
+            .to_owned(),
+        (Ok(_), false) => CreateEmbed::default()
+            .title("Alert resumed")
+            .description(format!("Alert #{alert_id} will send notifications again."))
+            .field("", "", false)
+            .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+            .color(0x2AC3DE)
+            .to_owned(),
+        (Err(error), true) => registry_error_embed(&error, "Unable to pause the alert. Please try again."),
+        (Err(error), false) => registry_error_embed(&error, "Unable to resume the alert. Please try again."),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("pause_alert").description("Mute an alert until it is resumed")
+        .create_option(|option| {
+            option
+                .name("alert_id")
+                .description("The ID of the alert to be paused. You can find it by using /list_alerts.")
+                .kind(CommandOptionType::Integer)
+                .required(true)
+                .min_int_value(0)
+        })
+}
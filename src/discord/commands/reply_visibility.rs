@@ -0,0 +1,74 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::Permissions;
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::Registry;
+
+// Options of the /reply_visibility command.
+#[derive(Debug, PartialEq)]
+pub struct ReplyVisibilityOptions {
+    pub public: bool,
+}
+
+impl FromOptions for ReplyVisibilityOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        match options.required_str("visibility")? {
+            "public" => Ok(Self { public: true }),
+            "private" => Ok(Self { public: false }),
+            _ => Err(OptionError::Invalid { name: "visibility", reason: "Must be `public` or `private`.".to_string() }),
+        }
+    }
+}
+
+pub async fn run(registry: &Registry, guild_id: Option<i64>, options: &[CommandDataOption]) -> CreateEmbed {
+    let ReplyVisibilityOptions { public } = match ReplyVisibilityOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            return CreateEmbed::default()
+            .title("Not available in direct messages")
+            .description("Replies in direct messages are always private.")
+            .color(0xDB4B4B)
+            .to_owned();
+        }
+    };
+
+    match registry.set_public_replies(guild_id, public).await {
+        Ok(_) => CreateEmbed::default()
+            .title("Reply visibility updated")
+            .description(match public {
+                true => "Replies to alert commands are now visible to the whole channel.",
+                false => "Replies to alert commands are now only visible to the user who sent the command.",
+            })
+            .color(0x2AC3DE)
+            .to_owned(),
+        Err(error) => registry_error_embed(&error, "Unable to update the server settings. Please try again."),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("reply_visibility").description("Choose whether replies to alert commands are visible to the whole channel")
+        // Only server managers should change how the bot behaves for everyone.
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|option| {
+            option
+                .name("visibility")
+                .description("Private replies are only visible to the user who sent the command.")
+                .kind(CommandOptionType::String)
+                .required(true)
+                .add_string_choice("Private", "private")
+                .add_string_choice("Public", "public")
+        })
+}
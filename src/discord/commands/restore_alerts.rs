@@ -0,0 +1,83 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use ethers::types::Address;
+use crate::discord::errors::registry_error_embed;
+use crate::discord::limits::Limits;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::Registry;
+use crate::registry::retention::Retention;
+use crate::registry::types::{Actor, RestoredAlerts};
+
+// Options of the /restore_alerts command.
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoreAlertsOptions {
+    // When missing, the deleted alerts of every cooler are restored.
+    pub cooler: Option<Address>,
+    pub loan_id: Option<i64>,
+}
+
+impl FromOptions for RestoreAlertsOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self { cooler: options.address("cooler")?, loan_id: options.i64("loan_id")? })
+    }
+}
+
+pub async fn run(registry: &Registry, limits: &Limits, retention: Retention, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
+    let RestoreAlertsOptions { cooler, loan_id } = match RestoreAlertsOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    match registry.restore_alerts(user_id, cooler, loan_id, &limits.quota, Actor::command(user_id)).await {
+        Ok(outcome) if outcome == RestoredAlerts::default() => CreateEmbed::default()
+            .title("No deleted alerts found")
+            .description(format!("Deleted alerts can only be restored within {} days.", retention.days))
+            .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+            .color(0xDB4B4B)
+            .to_owned(),
+        Ok(RestoredAlerts { restored, duplicates, over_quota }) => {
+            let mut embed = CreateEmbed::default();
+            embed.title("Alerts restored").field("Restored", restored, true);
+            // Alerts that couldn't come back are explained, so that the user knows what to do about them.
+            if duplicates > 0 {
+                embed.field("Already created again", duplicates, true);
+            }
+            if over_quota > 0 {
+                embed.field("Over your alert limit", over_quota, true);
+            }
+            embed
+                .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+                .color(if restored > 0 { 0x2AC3DE } else { 0xDB4B4B })
+                .to_owned()
+        },
+        Err(error) => registry_error_embed(&error, "Unable to restore the alerts. Please try again."),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("restore_alerts").description("Bring back recently deleted alerts.")
+        .create_option(|option| {
+            option
+                .name("cooler")
+                .description("Only restore the alerts of this Cooler contract. Must starts with `0x`.")
+                .kind(CommandOptionType::String)
+                .required(false)
+                .min_length(42) // enforce length of EVM address.
+                .max_length(42) // enforce length of EVM address.
+        })
+        .create_option(|option| {
+            option
+                .name("loan_id")
+                .description("Only restore the alerts of this loan.")
+                .kind(CommandOptionType::Integer)
+                .required(false)
+                .min_int_value(0)
+        })
+}
@@ -0,0 +1,45 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::discord::commands::pause_alert::set_paused;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::Registry;
+
+// Options of the /resume_alert command.
+#[derive(Debug, PartialEq)]
+pub struct ResumeAlertOptions {
+    pub alert_id: i64,
+}
+
+impl FromOptions for ResumeAlertOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self { alert_id: options.required_i64("alert_id")? })
+    }
+}
+
+pub async fn run(registry: &Registry, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
+    let ResumeAlertOptions { alert_id } = match ResumeAlertOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    set_paused(registry, user_id, alert_id, false).await
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("resume_alert").description("Unmute a paused or snoozed alert")
+        .create_option(|option| {
+            option
+                .name("alert_id")
+                .description("The ID of the alert to be resumed. You can find it by using /list_alerts.")
+                .kind(CommandOptionType::Integer)
+                .required(true)
+                .min_int_value(0)
+        })
+}
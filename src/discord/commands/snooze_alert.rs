@@ -0,0 +1,68 @@
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::duration::{format_duration, now, parse_duration};
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::registry::Registry;
+use crate::registry::types::Actor;
+
+// Options of the /snooze_alert command.
+#[derive(Debug, PartialEq)]
+pub struct SnoozeAlertOptions {
+    pub alert_id: i64,
+    // Snooze duration in seconds.
+    pub duration: i64,
+}
+
+impl FromOptions for SnoozeAlertOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        let duration = parse_duration(options.required_str("duration")?)
+            .map_err(|reason| OptionError::Invalid { name: "duration", reason })?;
+        Ok(Self { alert_id: options.required_i64("alert_id")?, duration })
+    }
+}
+
+pub async fn run(registry: &Registry, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
+    let SnoozeAlertOptions { alert_id, duration } = match SnoozeAlertOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    let snoozed_until = now().saturating_add(duration);
+    match registry.set_alert_paused_until(user_id, alert_id, Some(snoozed_until), Actor::command(user_id)).await {
+        Ok(_) => CreateEmbed::default()
+            .title("Alert snoozed")
+            .description(format!("Alert #{alert_id} won't send any notification for {}.", format_duration(duration as u64)))
+            .field("Snoozed until", format!("<t:{snoozed_until}:F>"), false)
+            .footer(|f| f.text("You can resume it earlier by using the slash command /resume_alert."))
+            .color(0x2AC3DE)
+            .to_owned(),
+        Err(error) => registry_error_embed(&error, "Unable to snooze the alert. Please try again."),
+    }
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("snooze_alert").description("Mute an alert for a while")
+        .create_option(|option| {
+            option
+                .name("alert_id")
+                .description("The ID of the alert to be snoozed. You can find it by using /list_alerts.")
+                .kind(CommandOptionType::Integer)
+                .required(true)
+                .min_int_value(0)
+        })
+        .create_option(|option| {
+            option
+                .name("duration")
+                .description("How long the alert should stay muted. Examples: 12h, 3d, 2w.")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+}
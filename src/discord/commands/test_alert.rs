@@ -0,0 +1,91 @@
+use lettre::transport::smtp::authentication::Credentials;
+use serenity::builder::{
+    CreateApplicationCommand,
+    CreateEmbed,
+};
+use serenity::model::prelude::{
+    command::CommandOptionType,
+    application_command::CommandDataOption,
+};
+use crate::address::format_address;
+use crate::discord::validation::redact_webhooks;
+use crate::discord::errors::registry_error_embed;
+use crate::discord::options::{FromOptions, OptionError, Options};
+use crate::listener::alerts::{send_email, send_webhook, Notice};
+use crate::registry::crypto::FieldCipher;
+use crate::registry::Registry;
+
+// Options of the /test_alert command.
+#[derive(Debug, PartialEq)]
+pub struct TestAlertOptions {
+    pub alert_id: i64,
+}
+
+impl FromOptions for TestAlertOptions {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError> {
+        let options = Options::new(options);
+        Ok(Self { alert_id: options.required_i64("alert_id")? })
+    }
+}
+
+pub async fn run(registry: &Registry, cipher: &FieldCipher, email_creds: Credentials, user_id: i64, options: &[CommandDataOption]) -> CreateEmbed {
+    let TestAlertOptions { alert_id } = match TestAlertOptions::from_options(options) {
+        Ok(options) => options,
+        Err(error) => return error.embed(),
+    };
+
+    let alert = match registry.get_user_alert(user_id, alert_id).await {
+        // Users can only test their own alerts.
+        Ok(alert) => alert,
+        Err(error) => return registry_error_embed(&error, "Unable to retrieve the alert form the DB. Please try again."),
+    };
+
+    let notice = Notice::Test { threshold: alert.get_threshold() as u64 };
+    let webhook_result = match alert.get_encrypted_webhook_url().map(|webhook_url| cipher.open(webhook_url)) {
+        Some(Ok(webhook_url)) => match send_webhook(&webhook_url, &notice, alert.get_cooler(), alert.get_loan_id()).await {
+            Ok(_) => ":white_check_mark: Sent".to_string(),
+            Err(error) => format!(":x: {}", redact_webhooks(&error.to_string())),
+        },
+        Some(Err(error)) => format!(":x: {error}"),
+        None => "Not configured".to_string(),
+    };
+    let email_result = match alert.get_encrypted_email().map(|email| cipher.open(email)) {
+        Some(Ok(receiver)) => match send_email(email_creds, &receiver, &notice, alert.get_cooler(), alert.get_loan_id()).await {
+            Ok(_) => ":white_check_mark: Sent".to_string(),
+            Err(error) => format!(":x: {error}"),
+        },
+        Some(Err(error)) => format!(":x: {error}"),
+        None => "Not configured".to_string(),
+    };
+
+    let failed = webhook_result.starts_with(":x:") || email_result.starts_with(":x:");
+    CreateEmbed::default()
+        .title(match failed {
+            true => "Test alert failed",
+            false => "Test alert sent",
+        })
+        .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler})", cooler = format_address(&alert.get_cooler())))
+        .field("Alert ID", alert_id, true)
+        .field("Loan ID", alert.get_loan_id(), true)
+        .field("", "", false)
+        .field("Webhook Notification", webhook_result, false)
+        .field("Email Notification", email_result, false)
+        .footer(|f| f.text("Check your webhook channel and inbox for a message marked as [TEST]."))
+        .color(match failed {
+            true => 0xDB4B4B,
+            false => 0x2AC3DE,
+        })
+        .to_owned()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.name("test_alert").description("Send a test notification through every channel of an alert")
+        .create_option(|option| {
+            option
+                .name("alert_id")
+                .description("The ID of the alert to be tested. You can find it by using /list_alerts.")
+                .kind(CommandOptionType::Integer)
+                .required(true)
+                .min_int_value(0)
+        })
+}
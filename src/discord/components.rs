@@ -0,0 +1,223 @@
+use std::sync::Arc;
+use serenity::builder::{CreateActionRow, CreateComponents, CreateEmbed};
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::{
+    message_component::MessageComponentInteraction,
+    modal::ModalSubmitInteraction,
+    InteractionResponseType,
+};
+use serenity::prelude::*;
+use ethers::providers::{Http, Provider};
+use crate::duration::{now, parse_duration};
+use crate::discord::commands::edit_alert::{edit, EditAlertOptions};
+use crate::discord::commands::list_alerts::{page as page_reply, parse_page_custom_id};
+use crate::registry::types::{Actor, AlertFilter, EventSource};
+use crate::discord::errors::registry_error_embed;
+use crate::registry::crypto::FieldCipher;
+use crate::registry::Registry;
+use crate::registry::retention::Retention;
+
+// Custom IDs of the alert buttons and modals follow the `alert:<action>:<alert_id>` format.
+const DELETE: &str = "delete";
+const SNOOZE: &str = "snooze";
+const EDIT_THRESHOLD: &str = "edit_threshold";
+
+// How long the "Snooze 1 day" button mutes an alert.
+const SNOOZE_DURATION: i64 = 24 * 3600;
+
+fn custom_id(action: &str, alert_id: i64) -> String {
+    format!("alert:{action}:{alert_id}")
+}
+
+fn parse_custom_id(custom_id: &str) -> Option<(&str, i64)> {
+    let mut parts = custom_id.split(':');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("alert"), Some(action), Some(alert_id), None) => Some((action, alert_id.parse().ok()?)),
+        _ => None,
+    }
+}
+
+// Row of buttons to manage an alert right from the message that shows it.
+pub fn alert_buttons(alert_id: i64) -> CreateActionRow {
+    CreateActionRow::default()
+        .create_button(|b| b.custom_id(custom_id(DELETE, alert_id)).label(format!("Delete #{alert_id}")).style(ButtonStyle::Danger))
+        .create_button(|b| b.custom_id(custom_id(SNOOZE, alert_id)).label("Snooze 1 day").style(ButtonStyle::Secondary))
+        .create_button(|b| b.custom_id(custom_id(EDIT_THRESHOLD, alert_id)).label("Edit threshold").style(ButtonStyle::Primary))
+        .to_owned()
+}
+
+// Handles a click on one of the alert buttons.
+pub async fn handle_button(ctx: &Context, registry: &Registry, retention: Retention, component: &MessageComponentInteraction) {
+    let user_id = component.user.id.0 as i64;
+    if let Some((owner_id, filter, page)) = parse_page_custom_id(&component.data.custom_id) {
+        return handle_page(ctx, registry, component, user_id == owner_id, (owner_id, filter, page)).await;
+    }
+    let (action, alert_id) = match parse_custom_id(&component.data.custom_id) {
+        Some(button) => button,
+        None => return,
+    };
+
+    // Only the owner of the alert can use its buttons, even if the message is visible to others.
+    let embed = match check_owner(registry, user_id, alert_id).await {
+        Err(embed) => embed,
+        Ok(_) if action == EDIT_THRESHOLD => {
+            // Ask for the new threshold, the change is applied when the modal is submitted.
+            if let Err(why) = component
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::Modal)
+                        .interaction_response_data(|modal| {
+                            modal
+                                .custom_id(custom_id(EDIT_THRESHOLD, alert_id))
+                                .title(format!("Edit alert #{alert_id}"))
+                                .components(|c| threshold_input(c))
+                        })
+                })
+                .await
+            {
+                println!("Cannot open modal: {}", why);
+            }
+            return;
+        },
+        Ok(_) if action == DELETE => match registry.delete_alert(user_id, alert_id, Actor::component(user_id)).await {
+            Ok(_) => CreateEmbed::default()
+                .title("Alert successfully deleted")
+                .description(format!("Alert #{alert_id} has been deleted. You can restore it with /restore_alerts within {} days.", retention.days))
+                .color(0x2AC3DE)
+                .to_owned(),
+            Err(error) => registry_error_embed(&error, "Unable to delete the alert. Please try again."),
+        },
+        Ok(_) if action == SNOOZE => {
+            let snoozed_until = now() + SNOOZE_DURATION;
+            match registry.set_alert_paused_until(user_id, alert_id, Some(snoozed_until), Actor::component(user_id)).await {
+                Ok(_) => CreateEmbed::default()
+                    .title("Alert snoozed")
+                    .description(format!("Alert #{alert_id} won't send any notification until <t:{snoozed_until}:F>."))
+                    .footer(|f| f.text("You can resume it earlier by using the slash command /resume_alert."))
+                    .color(0x2AC3DE)
+                    .to_owned(),
+                Err(error) => registry_error_embed(&error, "Unable to snooze the alert. Please try again."),
+            }
+        },
+        Ok(_) => return,
+    };
+
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.add_embed(embed).ephemeral(true))
+        })
+        .await
+    {
+        println!("Cannot respond to button: {}", why);
+    }
+}
+
+// Replaces the alert list with another page, as long as the list belongs to the clicking user.
+async fn handle_page(ctx: &Context, registry: &Registry, component: &MessageComponentInteraction, is_owner: bool, (owner_id, filter, page): (i64, AlertFilter, i64)) {
+    let result = match is_owner {
+        true => {
+            let reply = page_reply(registry, owner_id, &filter, page).await;
+            component
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|message| {
+                            message.set_embed(reply.embed);
+                            message.set_components(reply.components.unwrap_or_default())
+                        })
+                })
+                .await
+        },
+        false => {
+            component
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message
+                                .add_embed(CreateEmbed::default()
+                                    .title("This is not your list")
+                                    .description("You can check your own alerts by using the slash command /list_alerts.")
+                                    .color(0xDB4B4B)
+                                    .to_owned())
+                                .ephemeral(true)
+                        })
+                })
+                .await
+        },
+    };
+    if let Err(why) = result {
+        println!("Cannot respond to button: {}", why);
+    }
+}
+
+// Handles the submission of the modal opened by the "Edit threshold" button.
+pub async fn handle_modal(ctx: &Context, registry: &Registry, cipher: &FieldCipher, provider: Arc<Provider<Http>>, modal: &ModalSubmitInteraction) {
+    let user_id = modal.user.id.0 as i64;
+    let alert_id = match parse_custom_id(&modal.data.custom_id) {
+        Some((EDIT_THRESHOLD, alert_id)) => alert_id,
+        _ => return,
+    };
+
+    let threshold = modal_value(modal, "threshold").unwrap_or_default();
+
+    // Editing reads the loan and checks the webhook, which can take longer than discord waits for a response.
+    if let Err(why) = modal
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|message| message.ephemeral(true))
+        })
+        .await
+    {
+        println!("Cannot respond to modal: {}", why);
+        return;
+    }
+
+    let embed = match parse_duration(threshold) {
+        Ok(threshold) => edit(registry, cipher, provider, user_id, EventSource::Component, EditAlertOptions { alert_id, threshold: Some(threshold), webhook_url: None, email: None }).await,
+        Err(error) => CreateEmbed::default()
+            .title("Invalid threshold!")
+            .description(error)
+            .color(0xDB4B4B)
+            .to_owned(),
+    };
+
+    if let Err(why) = modal.edit_original_interaction_response(&ctx.http, |response| response.set_embed(embed)).await {
+        println!("Cannot respond to modal: {}", why);
+    }
+}
+
+// Value of a text input of a submitted modal, if it has been filled.
+pub fn modal_value<'a>(modal: &'a ModalSubmitInteraction, custom_id: &str) -> Option<&'a str> {
+    modal.data.components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == custom_id => Some(input.value.trim()),
+            _ => None,
+        })
+        .filter(|value| !value.is_empty())
+}
+
+fn threshold_input(components: &mut CreateComponents) -> &mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_input_text(|input| {
+            input
+                .custom_id("threshold")
+                .label("New threshold (e.g. 36h, 2d, 1w)")
+                .style(InputTextStyle::Short)
+                .required(true)
+        })
+    })
+}
+
+// Alerts of other users are reported as not found.
+async fn check_owner(registry: &Registry, user_id: i64, alert_id: i64) -> Result<(), CreateEmbed> {
+    match registry.get_user_alert(user_id, alert_id).await {
+        Ok(_) => Ok(()),
+        Err(error) => Err(registry_error_embed(&error, "Unable to retrieve the alert form the DB. Please try again.")),
+    }
+}
@@ -0,0 +1,45 @@
+use serenity::builder::CreateEmbed;
+use crate::registry::error::{QuotaScope, RegistryError};
+
+// User-facing embed for a registry error. `description` tells what the bot was trying to do,
+// and is only shown when the database itself failed.
+pub fn registry_error_embed(error: &RegistryError, description: &str) -> CreateEmbed {
+    match error {
+        RegistryError::NotFound => CreateEmbed::default()
+            .title("No alerts found")
+            .description("You don't have any matching alert.")
+            .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+            .color(0xDB4B4B)
+            .to_owned(),
+        RegistryError::Duplicate => CreateEmbed::default()
+            .title("Alert already exists")
+            .description("You already have an identical alert.")
+            .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
+            .color(0xDB4B4B)
+            .to_owned(),
+        RegistryError::Validation(reason) => CreateEmbed::default()
+            .title("Invalid alert!")
+            .description(reason)
+            .footer(|f| f.text("Please try again with a valid input format."))
+            .color(0xDB4B4B)
+            .to_owned(),
+        RegistryError::QuotaExceeded { scope, used, limit } => CreateEmbed::default()
+            .title("Quota reached")
+            .description(match scope {
+                QuotaScope::User => format!("You have {used} alerts, out of the {limit} allowed per user."),
+                QuotaScope::Guild => format!("This server has {used} alerts, out of the {limit} allowed per server."),
+            })
+            .footer(|f| f.text("Delete the alerts you no longer need with the slash command /delete_alerts, then try again."))
+            .color(0xDB4B4B)
+            .to_owned(),
+        RegistryError::Database(error) => {
+            // The raw error names tables and constraints, so it is only logged.
+            println!("Database error: {:?}", error);
+            CreateEmbed::default()
+                .title("Something went wrong!")
+                .description(description)
+                .color(0xDB4B4B)
+                .to_owned()
+        },
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serenity::builder::CreateEmbed;
+use crate::registry::types::AlertQuota;
+
+const DEFAULT_MAX_ALERTS_PER_USER: i64 = 50;
+const DEFAULT_MAX_ALERTS_PER_GUILD: i64 = 1000;
+const DEFAULT_CREATE_COOLDOWN_SECS: u64 = 10;
+
+// Limits on alert creation, so that a single user or guild can't flood the database and the RPC budget of the listener.
+pub struct Limits {
+    pub quota: AlertQuota,
+    pub cooldown: Cooldown,
+}
+
+impl Limits {
+    // Reads the limits from the `MAX_ALERTS_PER_USER`, `MAX_ALERTS_PER_GUILD` and `CREATE_ALERT_COOLDOWN` (seconds)
+    // env variables, falling back to the defaults when they are not set.
+    pub fn from_env() -> Self {
+        let quota = AlertQuota {
+            per_user: env_or("MAX_ALERTS_PER_USER", DEFAULT_MAX_ALERTS_PER_USER),
+            per_guild: env_or("MAX_ALERTS_PER_GUILD", DEFAULT_MAX_ALERTS_PER_GUILD),
+        };
+        let cooldown = Duration::from_secs(env_or("CREATE_ALERT_COOLDOWN", DEFAULT_CREATE_COOLDOWN_SECS));
+        Self { quota, cooldown: Cooldown::new(cooldown) }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(value) if !value.is_empty() => value.parse().unwrap_or_else(|_| panic!("invalid {name}")),
+        _ => default,
+    }
+}
+
+// Minimum time between two uses of the creation commands by the same user.
+pub struct Cooldown {
+    duration: Duration,
+    last_use: Mutex<HashMap<i64, Instant>>,
+}
+
+impl Cooldown {
+    pub fn new(duration: Duration) -> Self {
+        Self { duration, last_use: Mutex::new(HashMap::new()) }
+    }
+
+    // Returns how long the user still has to wait, if their cooldown isn't over yet.
+    pub fn check(&self, user_id: i64) -> Result<(), Duration> {
+        let last_use = self.last_use.lock().unwrap();
+        match last_use.get(&user_id).map(|used| used.elapsed()) {
+            Some(elapsed) if elapsed < self.duration => Err(self.duration - elapsed),
+            _ => Ok(()),
+        }
+    }
+
+    // Records a use by the user, starting their cooldown. Only successful creations count,
+    // so that fixing a typo isn't met with a cooldown.
+    pub fn record(&self, user_id: i64) {
+        let now = Instant::now();
+        let mut last_use = self.last_use.lock().unwrap();
+        // Forget the users whose cooldown is over, so that the map doesn't grow forever.
+        last_use.retain(|_, used| now.duration_since(*used) < self.duration);
+        last_use.insert(user_id, now);
+    }
+}
+
+pub fn cooldown_embed(remaining: Duration) -> CreateEmbed {
+    CreateEmbed::default()
+        .title("Slow down!")
+        .description(format!("Please wait {} more seconds before creating another alert.", remaining.as_secs().max(1)))
+        .color(0xDB4B4B)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cooldown_is_per_user() {
+        let cooldown = Cooldown::new(Duration::from_secs(60));
+        // Checking alone doesn't start the cooldown.
+        assert!(cooldown.check(1).is_ok());
+        assert!(cooldown.check(1).is_ok());
+        cooldown.record(1);
+        assert!(cooldown.check(2).is_ok());
+        assert!(cooldown.check(1).unwrap_err() <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn no_cooldown_when_disabled() {
+        let cooldown = Cooldown::new(Duration::ZERO);
+        cooldown.record(1);
+        assert!(cooldown.check(1).is_ok());
+    }
+}
@@ -1,24 +1,163 @@
+pub mod alert_file;
+pub mod autocomplete;
 pub mod commands;
+pub mod components;
+pub mod errors;
+pub mod limits;
+pub mod options;
+pub mod registration;
+pub mod validation;
 
+use std::sync::Arc;
+use std::time::Duration;
+use commands::create_alert::{CreateContext, PendingAlerts};
+use limits::Limits;
+use registration::CommandScope;
+use crate::registry::Registry;
+use crate::registry::crypto::FieldCipher;
+use crate::registry::retention::Retention;
+use ethers::providers::{Http, Provider};
+use lettre::transport::smtp::authentication::Credentials;
 use serenity::async_trait;
 use serenity::model::prelude::*;
-use serenity::builder::CreateEmbed;
-use serenity::model::application::command::Command;
-use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::application::interaction::{
+    application_command::ApplicationCommandInteraction,
+    Interaction,
+    InteractionResponseType,
+};
 use serenity::framework::standard::macros::group;
 use serenity::prelude::*;
-use sqlx;
+use tokio::sync::Notify;
 
 #[group]
 pub struct General;
 
+// Response to a slash command: an embed, optionally with buttons.
+pub struct Reply {
+    pub embed: CreateEmbed,
+    pub components: Option<CreateComponents>,
+}
+
+impl From<CreateEmbed> for Reply {
+    fn from(embed: CreateEmbed) -> Self {
+        Reply { embed, components: None }
+    }
+}
+
 pub struct Bot {
-    database: sqlx::SqlitePool,
+    registry: Registry,
+    cipher: Arc<FieldCipher>,
+    provider: Arc<Provider<Http>>,
+    email_creds: Credentials,
+    pending_alerts: PendingAlerts,
+    command_scope: CommandScope,
+    limits: Limits,
+    retention: Retention,
+    // Tells the listener to check the alerts again, see `WAKING_COMMANDS`.
+    wakeup: Arc<Notify>,
 }
 
+// Commands that call the RPC, SMTP server or discord webhooks and may not finish within the 3 seconds
+// discord waits for a reply. They are acknowledged right away and their response is edited afterwards.
+const DEFERRED_COMMANDS: [&str; 6] = ["create_alert", "edit_alert", "export_alerts", "import_alerts", "loan_status", "test_alert"];
+
+// How long a deferred command can run before giving up. Discord keeps the response editable for 15 minutes.
+pub(crate) const DEFERRED_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Commands that may create or re-arm an alert, or make it wake up earlier, after which the listener
+// checks the alerts again instead of waiting for its next planned check.
+const WAKING_COMMANDS: [&str; 6] = ["create_alert", "edit_alert", "import_alerts", "restore_alerts", "resume_alert", "snooze_alert"];
+
+// Commands that only show public on-chain data, so their replies follow the channel by default.
+const PUBLIC_COMMANDS: [&str; 1] = ["loan_status"];
+
 impl Bot {
-    pub fn new(database: sqlx::SqlitePool) -> Self {
-        Self { database }
+    pub fn new(registry: Registry, cipher: Arc<FieldCipher>, provider: Arc<Provider<Http>>, email_creds: Credentials, command_scope: CommandScope, limits: Limits, retention: Retention) -> Self {
+        Self { registry, cipher, provider, email_creds, pending_alerts: PendingAlerts::default(), command_scope, limits, retention, wakeup: Arc::new(Notify::new()) }
+    }
+
+    // Handle to wake up the listener, to be passed to `listener::monitor`.
+    pub fn wakeup(&self) -> Arc<Notify> {
+        self.wakeup.clone()
+    }
+
+    fn create_context(&self) -> CreateContext<'_> {
+        CreateContext { registry: &self.registry, cipher: &self.cipher, limits: &self.limits }
+    }
+
+    // Replies to alert commands are only visible to their author, unless the guild opted for public replies.
+    async fn is_ephemeral(&self, guild_id: Option<GuildId>) -> bool {
+        match guild_id {
+            Some(guild_id) => !self.registry.get_public_replies(guild_id.0 as i64).await.unwrap_or(false),
+            None => true,
+        }
+    }
+
+    async fn run_command(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> Reply {
+        let user_id = command.user.id.0 as i64;
+        let guild_id = command.guild_id.map(|guild_id| guild_id.0 as i64);
+        let reply = match command.data.name.as_str() {
+            "list_alerts" => commands::list_alerts::run(&self.registry, user_id, &command.data.options).await,
+            "create_alert" => commands::create_alert::run(&self.create_context(), user_id, guild_id, &command.data.options).await.into(),
+            "delete_alerts" => commands::delete_alerts::run(&self.registry, self.retention, user_id, &command.data.options).await.into(),
+            "restore_alerts" => commands::restore_alerts::run(&self.registry, &self.limits, self.retention, user_id, &command.data.options).await.into(),
+            "loan_status" => commands::loan_status::run(self.provider.clone(), &command.data.options).await.into(),
+            "edit_alert" => commands::edit_alert::run(&self.registry, &self.cipher, self.provider.clone(), user_id, &command.data.options).await.into(),
+            "pause_alert" => commands::pause_alert::run(&self.registry, user_id, &command.data.options).await.into(),
+            "resume_alert" => commands::resume_alert::run(&self.registry, user_id, &command.data.options).await.into(),
+            "snooze_alert" => commands::snooze_alert::run(&self.registry, user_id, &command.data.options).await.into(),
+            "test_alert" => commands::test_alert::run(&self.registry, &self.cipher, self.email_creds.clone(), user_id, &command.data.options).await.into(),
+            "reply_visibility" => commands::reply_visibility::run(&self.registry, guild_id, &command.data.options).await.into(),
+            "alert_history" => commands::alert_history::run(&self.registry, user_id, guild_id, &command.data.options).await.into(),
+            "export_alerts" => commands::export_alerts::run(ctx, &self.registry, &self.cipher, &command.user).await.into(),
+            "import_alerts" => commands::import_alerts::run(&self.create_context(), user_id, guild_id, &command.data).await.into(),
+            "my_data" => commands::my_data::run(&self.registry, self.retention, user_id).await.into(),
+            "forget_me" => commands::forget_me::run(),
+            _ => CreateEmbed::default().title("not implemented :(").to_owned().into(),
+        };
+        if WAKING_COMMANDS.contains(&command.data.name.as_str()) {
+            self.wakeup.notify_one();
+        }
+        reply
+    }
+
+    // Acknowledges the command right away, then replaces the "thinking" message with the reply once it is ready.
+    async fn respond_deferred(&self, ctx: &Context, command: &ApplicationCommandInteraction, ephemeral: bool) {
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                    .interaction_response_data(|message| message.ephemeral(ephemeral))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {}", why);
+            return;
+        }
+
+        let reply = match tokio::time::timeout(DEFERRED_TIMEOUT, self.run_command(ctx, command)).await {
+            Ok(reply) => reply,
+            Err(_) => CreateEmbed::default()
+                .title("Something went wrong!")
+                .description("The command took too long to complete. Please try again later.")
+                .color(0xDB4B4B)
+                .to_owned()
+                .into(),
+        };
+
+        if let Err(why) = command
+            .edit_original_interaction_response(&ctx.http, |response| {
+                response.set_embed(reply.embed);
+                if let Some(components) = reply.components {
+                    response.set_components(components);
+                }
+                response
+            })
+            .await
+        {
+            println!("Cannot edit slash command response: {}", why);
+        }
     }
 }
 
@@ -28,46 +167,76 @@ impl EventHandler for Bot {
     async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
 
-        let bot_commands = Command::set_global_application_commands(&ctx.http, |commands| {
-            commands
-                .create_application_command(|command| {
-                    commands::create_alert::register(command)
-                })
-                .create_application_command(|command| {
-                    commands::list_alerts::register(command)
-                })
-                .create_application_command(|command| {
-                    commands::delete_alerts::register(command)
-                })
-        }).await;
-
-        println!("The following global slash commands have been created: {:#?}", bot_commands);
+        registration::register_commands(&ctx, &self.registry, self.command_scope).await;
     }
 
     // Setup functions to handle the slash commands.
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(command) = interaction {
-            println!("Received command interaction: {:#?}", command);
-
-            let user_id = command.user.id.0 as i64;
-
-            let embed = match command.data.name.as_str() {
-                "list_alerts" => commands::list_alerts::run(&self.database, user_id).await,
-                "create_alert" => commands::create_alert::run(&self.database, user_id, &command.data.options).await,
-                "delete_alerts" => commands::delete_alerts::run(&self.database, user_id, &command.data.options).await,
-                _ => CreateEmbed::default().title("not implemented :(").to_owned(),
-            };
-
-            if let Err(why) = command
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::ChannelMessageWithSource)
-                        .interaction_response_data(|message| message.add_embed(embed))
-                })
-                .await
-            {
-                println!("Cannot respond to slash command: {}", why);
-            }
+        match interaction {
+            Interaction::ApplicationCommand(command) => {
+                // Only the name is logged, as the options may hold webhook URLs and emails.
+                println!("Received command interaction: /{} from {}", command.data.name, command.user.id);
+
+                if command.data.name == "create_alert" && command.data.options.is_empty() {
+                    return commands::create_alert::open_modal(&ctx, &command).await;
+                }
+
+                let ephemeral = match command.data.name.as_str() {
+                    name if PUBLIC_COMMANDS.contains(&name) => false,
+                    "reply_visibility" | "alert_history" | "export_alerts" | "import_alerts" | "my_data" | "forget_me" => true,
+                    _ => self.is_ephemeral(command.guild_id).await,
+                };
+
+                match DEFERRED_COMMANDS.contains(&command.data.name.as_str()) {
+                    true => self.respond_deferred(&ctx, &command, ephemeral).await,
+                    false => {
+                        let reply = self.run_command(&ctx, &command).await;
+                        if let Err(why) = command
+                            .create_interaction_response(&ctx.http, |response| {
+                                response
+                                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                                    .interaction_response_data(|message| {
+                                        message.add_embed(reply.embed).ephemeral(ephemeral);
+                                        if let Some(components) = reply.components {
+                                            message.set_components(components);
+                                        }
+                                        message
+                                    })
+                            })
+                            .await
+                        {
+                            println!("Cannot respond to slash command: {}", why);
+                        }
+                    },
+                }
+            },
+            Interaction::MessageComponent(component) => {
+                println!("Received component interaction: {}", component.data.custom_id);
+                match component.data.custom_id.as_str() {
+                    commands::create_alert::LOAN_SELECT_ID => {
+                        commands::create_alert::handle_loan_select(&ctx, &self.create_context(), &self.pending_alerts, &component).await;
+                        self.wakeup.notify_one();
+                    },
+                    commands::forget_me::CONFIRM_ID | commands::forget_me::CANCEL_ID => commands::forget_me::handle_button(&ctx, &self.registry, &self.pending_alerts, &component).await,
+                    _ => components::handle_button(&ctx, &self.registry, self.retention, &component).await,
+                }
+            },
+            Interaction::ModalSubmit(modal) => {
+                println!("Received modal interaction: {}", modal.data.custom_id);
+                match modal.data.custom_id.as_str() {
+                    commands::create_alert::MODAL_ID => {
+                        let ephemeral = self.is_ephemeral(modal.guild_id).await;
+                        commands::create_alert::handle_modal(&ctx, &self.create_context(), self.provider.clone(), &self.pending_alerts, &modal, ephemeral).await
+                    },
+                    _ => components::handle_modal(&ctx, &self.registry, &self.cipher, self.provider.clone(), &modal).await,
+                }
+                // Both forms create or edit an alert.
+                self.wakeup.notify_one();
+            },
+            Interaction::Autocomplete(autocomplete) => {
+                autocomplete::handle(&ctx, &self.registry, self.provider.clone(), &autocomplete).await;
+            },
+            _ => (),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+use ethers::types::Address;
+use serenity::builder::CreateEmbed;
+use serenity::json::Value;
+use serenity::model::prelude::application_command::CommandDataOption;
+use crate::address::parse_address;
+
+// Error found when reading the options of a slash command.
+#[derive(Debug, PartialEq)]
+pub enum OptionError {
+    // A required option wasn't provided.
+    Missing(&'static str),
+    // The option was provided with a value of the wrong type.
+    WrongType { name: &'static str, expected: &'static str },
+    // The option has the right type but its content is not acceptable.
+    Invalid { name: &'static str, reason: String },
+}
+
+impl std::error::Error for OptionError {}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::Missing(name) => write!(f, "The option `{name}` is required."),
+            OptionError::WrongType { name, expected } => write!(f, "The option `{name}` must be {expected}."),
+            OptionError::Invalid { name, reason } => write!(f, "Invalid `{name}`: {reason}"),
+        }
+    }
+}
+
+impl OptionError {
+    // User-facing embed describing the error.
+    pub fn embed(&self) -> CreateEmbed {
+        CreateEmbed::default()
+            .title("Invalid command options!")
+            .description(self.to_string())
+            .footer(|f| f.text("Please try again with a valid input format."))
+            .color(0xDB4B4B)
+            .to_owned()
+    }
+}
+
+// Options of a slash command that can be built from the raw interaction data.
+pub trait FromOptions: Sized {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, OptionError>;
+}
+
+// Lookup of slash command options by name, so that their order doesn't matter.
+pub struct Options<'a>(&'a [CommandDataOption]);
+
+impl<'a> Options<'a> {
+    pub fn new(options: &'a [CommandDataOption]) -> Self {
+        Self(options)
+    }
+
+    fn value(&self, name: &str) -> Option<&'a Value> {
+        self.0
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_ref())
+            .filter(|value| !value.is_null())
+    }
+
+    pub fn str(&self, name: &'static str) -> Result<Option<&'a str>, OptionError> {
+        match self.value(name) {
+            Some(value) => match value.as_str() {
+                Some(value) => Ok(Some(value)),
+                None => Err(OptionError::WrongType { name, expected: "a text" }),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn i64(&self, name: &'static str) -> Result<Option<i64>, OptionError> {
+        match self.value(name) {
+            Some(value) => match value.as_i64() {
+                Some(value) => Ok(Some(value)),
+                None => Err(OptionError::WrongType { name, expected: "an integer" }),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn bool(&self, name: &'static str) -> Result<Option<bool>, OptionError> {
+        match self.value(name) {
+            Some(value) => match value.as_bool() {
+                Some(value) => Ok(Some(value)),
+                None => Err(OptionError::WrongType { name, expected: "true or false" }),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn address(&self, name: &'static str) -> Result<Option<Address>, OptionError> {
+        self.str(name)?
+            .map(|value| parse_address(value).map_err(|reason| OptionError::Invalid { name, reason }))
+            .transpose()
+    }
+
+    // User options are sent as the ID of the user.
+    pub fn user_id(&self, name: &'static str) -> Result<Option<i64>, OptionError> {
+        match self.str(name)? {
+            Some(user_id) => user_id.parse().map(Some).map_err(|_| OptionError::WrongType { name, expected: "a user" }),
+            None => Ok(None),
+        }
+    }
+
+    pub fn required_str(&self, name: &'static str) -> Result<&'a str, OptionError> {
+        self.str(name)?.ok_or(OptionError::Missing(name))
+    }
+
+    pub fn required_i64(&self, name: &'static str) -> Result<i64, OptionError> {
+        self.i64(name)?.ok_or(OptionError::Missing(name))
+    }
+
+    pub fn required_address(&self, name: &'static str) -> Result<Address, OptionError> {
+        self.address(name)?.ok_or(OptionError::Missing(name))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serenity::json::json;
+
+    // Builds an option the way discord sends it. `kind` follows discord's option types (3 = string, 4 = integer, 5 = boolean).
+    pub fn option(name: &str, kind: u8, value: Value) -> CommandDataOption {
+        serde_json::from_value(json!({ "name": name, "type": kind, "value": value })).unwrap()
+    }
+
+    #[test]
+    fn finds_options_by_name_in_any_order() {
+        let raw = vec![
+            option("loan_id", 4, json!(3)),
+            option("cooler", 3, json!("0xabc")),
+        ];
+        let options = Options::new(&raw);
+        assert_eq!(options.required_str("cooler"), Ok("0xabc"));
+        assert_eq!(options.required_i64("loan_id"), Ok(3));
+    }
+
+    #[test]
+    fn missing_options() {
+        let raw = vec![option("cooler", 3, json!("0xabc"))];
+        let options = Options::new(&raw);
+        assert_eq!(options.i64("loan_id"), Ok(None));
+        assert_eq!(options.required_i64("loan_id"), Err(OptionError::Missing("loan_id")));
+        assert_eq!(options.bool("post_expiry"), Ok(None));
+    }
+
+    #[test]
+    fn null_values_are_missing() {
+        let raw = vec![option("email", 3, Value::Null)];
+        let options = Options::new(&raw);
+        assert_eq!(options.str("email"), Ok(None));
+    }
+
+    #[test]
+    fn wrong_types() {
+        let raw = vec![
+            option("loan_id", 3, json!("three")),
+            option("post_expiry", 3, json!("yes")),
+        ];
+        let options = Options::new(&raw);
+        assert_eq!(options.i64("loan_id"), Err(OptionError::WrongType { name: "loan_id", expected: "an integer" }));
+        assert_eq!(options.bool("post_expiry"), Err(OptionError::WrongType { name: "post_expiry", expected: "true or false" }));
+    }
+}
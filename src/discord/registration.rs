@@ -0,0 +1,88 @@
+use std::fmt;
+use serenity::builder::CreateApplicationCommands;
+use serenity::model::application::command::Command;
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use crate::discord::commands;
+use crate::registry::Registry;
+
+// Where the slash commands are registered. Guild commands show up instantly, which is handy for a
+// test server, while global commands can take up to an hour to reach every server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandScope {
+    Global,
+    Guild(GuildId),
+}
+
+impl CommandScope {
+    // Reads the scope from the `COMMANDS_GUILD_ID` env variable, defaulting to global commands.
+    pub fn from_env() -> Self {
+        match std::env::var("COMMANDS_GUILD_ID") {
+            Ok(guild_id) if !guild_id.is_empty() => {
+                CommandScope::Guild(GuildId(guild_id.parse().expect("invalid COMMANDS_GUILD_ID")))
+            },
+            _ => CommandScope::Global,
+        }
+    }
+}
+
+impl fmt::Display for CommandScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandScope::Global => write!(f, "global"),
+            CommandScope::Guild(guild_id) => write!(f, "guild:{}", guild_id.0),
+        }
+    }
+}
+
+// Definitions of every slash command of the bot.
+fn bot_commands(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    commands
+        .create_application_command(|command| commands::create_alert::register(command))
+        .create_application_command(|command| commands::list_alerts::register(command))
+        .create_application_command(|command| commands::delete_alerts::register(command))
+        .create_application_command(|command| commands::loan_status::register(command))
+        .create_application_command(|command| commands::test_alert::register(command))
+        .create_application_command(|command| commands::edit_alert::register(command))
+        .create_application_command(|command| commands::pause_alert::register(command))
+        .create_application_command(|command| commands::resume_alert::register(command))
+        .create_application_command(|command| commands::snooze_alert::register(command))
+        .create_application_command(|command| commands::reply_visibility::register(command))
+        .create_application_command(|command| commands::alert_history::register(command))
+        .create_application_command(|command| commands::restore_alerts::register(command))
+        .create_application_command(|command| commands::export_alerts::register(command))
+        .create_application_command(|command| commands::import_alerts::register(command))
+        .create_application_command(|command| commands::my_data::register(command))
+        .create_application_command(|command| commands::forget_me::register(command))
+}
+
+// Registers the slash commands in the given scope, unless the same definitions were already registered there.
+pub async fn register_commands(ctx: &Context, registry: &Registry, scope: CommandScope) {
+    let mut definitions = CreateApplicationCommands::default();
+    bot_commands(&mut definitions);
+    let definition = serenity::json::Value::from(definitions.0).to_string();
+
+    let scope_key = scope.to_string();
+    match registry.get_registered_commands(&scope_key).await {
+        Ok(Some(registered)) if registered == definition => {
+            println!("The {scope} slash commands are up to date.");
+            return;
+        },
+        Ok(_) => (),
+        Err(error) => println!("Unable to read the registered slash commands, registering them again: {}", error),
+    }
+
+    let result = match scope {
+        CommandScope::Global => Command::set_global_application_commands(&ctx.http, bot_commands).await,
+        CommandScope::Guild(guild_id) => guild_id.set_application_commands(&ctx.http, bot_commands).await,
+    };
+    match result {
+        Ok(bot_commands) => {
+            println!("The following {scope} slash commands have been created: {:#?}", bot_commands);
+            if let Err(error) = registry.set_registered_commands(&scope_key, &definition).await {
+                println!("Unable to store the registered slash commands: {}", error);
+            }
+        },
+        Err(why) => println!("Cannot register the {scope} slash commands: {}", why),
+    }
+}
@@ -0,0 +1,124 @@
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::model::webhook::Webhook;
+use serenity::utils::parse_webhook;
+use url::Url;
+
+// Error found when validating a user input, tied to the option that caused it.
+#[derive(Debug)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self { field, message: message.into() }
+    }
+}
+
+// Checks that the input is a valid email address, returning it normalized.
+pub fn validate_email(input: &str) -> Result<String, FieldError> {
+    match input.trim().parse::<lettre::Address>() {
+        Ok(address) => Ok(address.to_string()),
+        Err(error) => Err(FieldError::new("email", format!("`{input}` is not a valid email address ({error}). Example: cooler_alerts@yxz.com"))),
+    }
+}
+
+// Checks that the input is a discord webhook URL and that the webhook still exists.
+pub async fn validate_webhook_url(input: &str) -> Result<String, FieldError> {
+    let invalid = || FieldError::new(
+        "webhook_url",
+        "This is not a discord webhook URL. Example: https://discord.com/api/webhooks/123/XXX",
+    );
+
+    let url = Url::parse(input.trim()).map_err(|_| invalid())?;
+    if url.scheme() != "https" || parse_webhook(&url).is_none() {
+        return Err(invalid());
+    }
+
+    // Make sure the webhook exists, so that a typo doesn't go unnoticed until the alert triggers.
+    let http = Http::new("");
+    match Webhook::from_url(&http, url.as_str()).await {
+        Ok(_) => Ok(url.to_string()),
+        Err(error) => Err(FieldError::new("webhook_url", format!("The webhook couldn't be reached, it may have been deleted ({}).", redact_webhooks(&error.to_string())))),
+    }
+}
+
+// Hides the token of any webhook URL found in the text, so that error messages never echo it back.
+pub fn redact_webhooks(text: &str) -> String {
+    const MARKER: &str = "/webhooks/";
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(MARKER) {
+        let (before, after) = rest.split_at(start + MARKER.len());
+        redacted.push_str(before);
+        let id_len = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+        redacted.push_str(&after[..id_len]);
+        rest = &after[id_len..];
+        if let Some(token) = rest.strip_prefix('/') {
+            let token_len = token.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_')).unwrap_or(token.len());
+            if token_len > 0 {
+                redacted.push_str("/***");
+                rest = &token[token_len..];
+            }
+        }
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+// Validates every receiver provided by the user, so that all the errors can be fixed at once.
+pub async fn validate_receivers(webhook_url: Option<&str>, email: Option<&str>) -> Result<(Option<String>, Option<String>), Vec<FieldError>> {
+    let mut errors: Vec<FieldError> = Vec::new();
+    let webhook_url = match webhook_url {
+        Some(webhook_url) => match validate_webhook_url(webhook_url).await {
+            Ok(webhook_url) => Some(webhook_url),
+            Err(error) => {
+                errors.push(error);
+                None
+            }
+        },
+        None => None,
+    };
+    let email = match email.map(validate_email) {
+        Some(Ok(email)) => Some(email),
+        Some(Err(error)) => {
+            errors.push(error);
+            None
+        },
+        None => None,
+    };
+
+    match errors.is_empty() {
+        true => Ok((webhook_url, email)),
+        false => Err(errors),
+    }
+}
+
+// User-facing embed listing the invalid receivers.
+pub fn receivers_error_embed(errors: Vec<FieldError>) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .title("Invalid alert receiver!")
+        .description("Please try again with a valid input format.")
+        .color(0xDB4B4B)
+        .to_owned();
+    for error in errors {
+        embed.field(error.field, error.message, false);
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_webhook_tokens() {
+        assert_eq!(
+            redact_webhooks("error sending request for url (https://discord.com/api/webhooks/123/abc-DEF_9?wait=true): timeout"),
+            "error sending request for url (https://discord.com/api/webhooks/123/***?wait=true): timeout",
+        );
+        assert_eq!(redact_webhooks("Unknown Webhook"), "Unknown Webhook");
+    }
+}
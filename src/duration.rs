@@ -0,0 +1,145 @@
+use chrono::{TimeZone, Utc};
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+// Parses a user provided duration such as "36h", "2d", "1w" or "1d12h" into seconds.
+// A bare number is interpreted as days, so that the old integer thresholds keep working.
+pub fn parse_duration(input: &str) -> Result<i64, String> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Err("The duration can't be empty.".to_string());
+    }
+    if let Ok(days) = input.parse::<i64>() {
+        return days.checked_mul(DAY).filter(|s| *s >= 0).ok_or(format!("Invalid duration: `{input}`."));
+    }
+
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => MINUTE,
+            'h' => HOUR,
+            'd' => DAY,
+            'w' => WEEK,
+            _ => return Err(format!("Unknown unit `{c}` in `{input}`. Use `s`, `m`, `h`, `d` or `w` (e.g. `36h`, `2d`, `1w`).")),
+        };
+        let value: i64 = number.parse().map_err(|_| format!("Missing number before `{c}` in `{input}`."))?;
+        number.clear();
+        total = value
+            .checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or(format!("Duration `{input}` is too large."))?;
+    }
+    if !number.is_empty() {
+        return Err(format!("Missing unit after `{number}` in `{input}`. Use `s`, `m`, `h`, `d` or `w` (e.g. `36h`, `2d`, `1w`)."));
+    }
+
+    Ok(total)
+}
+
+// Formats a number of seconds using its two most significant units, e.g. "1d 12h" or "23h 5m".
+pub fn format_duration(seconds: u64) -> String {
+    let seconds = seconds as i64;
+    if seconds < MINUTE {
+        return format!("{seconds}s");
+    }
+
+    let units = [(DAY, "d"), (HOUR, "h"), (MINUTE, "m")];
+    let mut remaining = seconds;
+    let mut parts: Vec<String> = Vec::new();
+    for (size, label) in units {
+        if parts.len() == 2 {
+            break;
+        }
+        let amount = remaining / size;
+        remaining %= size;
+        if amount > 0 {
+            parts.push(format!("{amount}{label}"));
+        } else if !parts.is_empty() {
+            // Stop at the first gap, e.g. 1 day and 5 minutes is shown as "1d".
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+// Formats a number of seconds without rounding, e.g. "1d12h" or "2h30s", in a form that `parse_duration` reads back.
+pub fn format_exact_duration(seconds: i64) -> String {
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+    let mut remaining = seconds;
+    let mut formatted = String::new();
+    for (size, label) in [(WEEK, "w"), (DAY, "d"), (HOUR, "h"), (MINUTE, "m"), (1, "s")] {
+        let amount = remaining / size;
+        remaining %= size;
+        if amount > 0 {
+            formatted.push_str(&format!("{amount}{label}"));
+        }
+    }
+    formatted
+}
+
+// Formats a unix timestamp as an absolute UTC date, for channels that can't render discord timestamps.
+pub fn format_timestamp(timestamp: i64) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(date) => date.format("%Y-%m-%d %H:%M UTC").to_string(),
+        None => format!("unix time {timestamp}"),
+    }
+}
+
+// Current unix timestamp in seconds.
+pub fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_units_and_combinations() {
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("90m"), Ok(90 * MINUTE));
+        assert_eq!(parse_duration("36h"), Ok(36 * HOUR));
+        assert_eq!(parse_duration("2d"), Ok(2 * DAY));
+        assert_eq!(parse_duration("1w"), Ok(WEEK));
+        assert_eq!(parse_duration(" 1D 12h "), Ok(DAY + 12 * HOUR));
+        // Plain numbers are days, as the thresholds used to be.
+        assert_eq!(parse_duration("3"), Ok(3 * DAY));
+        assert_eq!(parse_duration("0"), Ok(0));
+        assert_eq!(parse_duration("0h"), Ok(0));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-1").is_err());
+        assert!(parse_duration("h").unwrap_err().contains("Missing number"));
+        assert!(parse_duration("1d12").unwrap_err().contains("Missing unit"));
+        assert!(parse_duration("2y").unwrap_err().contains("Unknown unit"));
+        assert!(parse_duration("99999999999999999w").unwrap_err().contains("too large"));
+        assert!(parse_duration("999999999999999999").is_err());
+    }
+
+    #[test]
+    fn formats_the_two_most_significant_units() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(59), "59s");
+        assert_eq!(format_duration(90 * MINUTE as u64), "1h 30m");
+        assert_eq!(format_duration((DAY + 12 * HOUR + 5 * MINUTE) as u64), "1d 12h");
+        assert_eq!(format_duration((DAY + 5 * MINUTE) as u64), "1d");
+        assert_eq!(format_exact_duration(DAY + 5 * MINUTE), "1d5m");
+    }
+}
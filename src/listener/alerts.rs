@@ -1,3 +1,4 @@
+use std::error::Error;
 use serenity::http::Http;
 use serenity::model::{
     webhook::Webhook,
@@ -8,48 +9,115 @@ use lettre::{
     transport::smtp::authentication::Credentials,
     Message, SmtpTransport, Transport,
 };
+use ethers::types::Address;
+use crate::address::format_address;
+use crate::duration::{format_duration, format_timestamp};
+
+// Name shown as the sender of the notifications.
+const BOT_NAME: &str = "Cooler Alerts";
+
+// Kind of notification sent to the user.
+pub enum Notice {
+    // The loan is about to expire.
+    Expiring { time_left: u64, expiry: i64 },
+    // The loan has expired and hasn't been repaid nor claimed yet.
+    Overdue { overdue: u64, expiry: i64 },
+    // Sample notification requested by the user to check the alert channels.
+    Test { threshold: u64 },
+}
+
+impl Notice {
+    fn title(&self) -> &'static str {
+        match self {
+            Notice::Expiring { .. } => "New Alert!",
+            Notice::Overdue { .. } => "Loan Expired!",
+            Notice::Test { .. } => "[TEST] Test Alert",
+        }
+    }
+
+    fn headline(&self) -> &'static str {
+        match self {
+            Notice::Expiring { .. } => "is about to expire!",
+            Notice::Overdue { .. } => "has expired and can be claimed by the lender at any time!",
+            Notice::Test { .. } => "is being monitored. This is a test message, no action is required.",
+        }
+    }
+
+    // Label and value of the time related field.
+    fn time(&self) -> (&'static str, String) {
+        match self {
+            Notice::Expiring { time_left, .. } => ("Time Left", format_duration(*time_left)),
+            Notice::Overdue { overdue, .. } => ("Expired For", format_duration(*overdue)),
+            Notice::Test { threshold } => ("Alert threshold", format_duration(*threshold)),
+        }
+    }
+
+    fn expiry(&self) -> Option<i64> {
+        match self {
+            Notice::Expiring { expiry, .. } | Notice::Overdue { expiry, .. } => Some(*expiry),
+            Notice::Test { .. } => None,
+        }
+    }
+}
 
 // Public method to send alerts via discord webhooks.
-pub async fn send_webhook(webhook_url: &str, cooler: &str, loan_id: i64, days: u64) {
+pub async fn send_webhook(webhook_url: &str, notice: &Notice, cooler: Address, loan_id: i64) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let cooler = format_address(&cooler);
     let http = Http::new("");
-    let webhook = Webhook::from_url(&http, &webhook_url).await.unwrap();
+    let webhook = Webhook::from_url(&http, webhook_url).await?;
+    let (time_label, time) = notice.time();
     let embed = Embed::fake(|e| {
-        e.title(format!("New Alert!"))
-            .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler}) is about to expire!"))
+        e.title(notice.title())
+            .description(format!("Cooler Contract: [{cooler}](https://www.etherscan.io/address/{cooler}) {}", notice.headline()))
             .field("Loan ID", loan_id, true)
-            .field("Time Left", format!("{days} days"), true)
-            .field(" ", " ", false)
+            .field(time_label, time, true);
+        if let Some(expiry) = notice.expiry() {
+            e.field("Expiry", format!("<t:{expiry}:F>"), true);
+        }
+        e.field(" ", " ", false)
             .footer(|f| f.text("Remember that you can check your current alerts by using the slash command /list_alerts."))
-            .color(0xDB4B4B)
+            .color(match notice {
+                Notice::Test { .. } => 0x2AC3DE,
+                _ => 0xDB4B4B,
+            })
     });
 
+    // No buttons here: discord only allows interactive components on webhooks owned by the bot application,
+    // while alert webhooks are created by the users. Alerts can be managed from /list_alerts instead.
     webhook
         .execute(&http, false, |w| {
             w.content("")
-                .username("Webhook test")
+                .username(BOT_NAME)
                 .embeds(vec![embed])
         })
-        .await
-        .expect("Could not execute webhook.");
+        .await?;
+    Ok(())
 }
 
 // Public method to send alerts via an email.
-pub async fn send_email(creds: Credentials, receiver: &str, cooler: &str, loan_id: i64, days: u64) {
+pub async fn send_email(creds: Credentials, receiver: &str, notice: &Notice, cooler: Address, loan_id: i64) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let cooler = format_address(&cooler);
+    let (time_label, time) = notice.time();
+    let expiry = match notice.expiry() {
+        Some(expiry) => format!("\n- Expiry: {}", format_timestamp(expiry)),
+        None => String::new(),
+    };
     let email = Message::builder()
-        .from("Cooler Monitoring <test@gmail.com>".parse().unwrap())
-        .to(receiver.parse().unwrap())
-        .subject("New Cooler Alert!")
+        .from(format!("{BOT_NAME} <test@gmail.com>").parse()?)
+        .to(receiver.parse()?)
+        .subject(format!("Cooler Alert: {}", notice.title()))
         .header(ContentType::TEXT_PLAIN)
-        .body(format!("Cooler: {cooler} is about to expire!\n- Loan ID: {loan_id}\n- Time Left: {days} days\n\nhttps://www.etherscan.io/address/{cooler}", cooler=cooler, loan_id=loan_id, days=days))
-        .unwrap();
+        .body(format!(
+            "Cooler: {cooler} {headline}\n- Loan ID: {loan_id}\n- {time_label}: {time}{expiry}\n\nhttps://www.etherscan.io/address/{cooler}",
+            cooler=cooler, headline=notice.headline(), loan_id=loan_id, time_label=time_label, time=time, expiry=expiry
+        ))?;
 
-    let smtp = SmtpTransport::relay("smtp.gmail.com")
-        .unwrap()
+    let smtp = SmtpTransport::relay("smtp.gmail.com")?
         .credentials(creds)
         .build();
 
-    match smtp.send(&email) {
-        Ok(_) => println!("Email sent successfully!"),
-        Err(e) => panic!("Could not send email: {:?}", e),
-    }
-}
\ No newline at end of file
+    // The SMTP transport blocks, so it runs on its own thread instead of stalling the listener and the bot.
+    tokio::task::spawn_blocking(move || smtp.send(&email)).await??;
+    println!("Email sent successfully!");
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use ethers::{
+    contract::ContractError,
+    prelude::abigen,
+    providers::{Http, Provider},
+    types::{Address, U256},
+    utils::format_units,
+};
+use crate::duration::now;
+
+abigen!(
+    ICooler,
+    r#"[
+        struct Request { uint256 amount; uint256 interest; uint256 loanToCollateral; uint256 duration; bool active; address requester; }
+        struct Loan { Request request; uint256 principal; uint256 interestDue; uint256 collateral; uint256 expiry; address lender; address recipient; bool callback; }
+        function getLoan(uint256 loanID_) external view returns (Loan memory)
+        function collateral() external view returns (address)
+        function debt() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IERC20,
+    r#"[
+        function symbol() external view returns (string)
+        function decimals() external view returns (uint8)
+    ]"#
+);
+
+// Lifecycle of a Cooler loan, as seen from the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoanStatus {
+    // The loan is running and can be repaid or rolled over.
+    Active,
+    // The loan is past its expiry but the lender hasn't claimed the collateral yet.
+    Expired,
+    // The debt has been fully repaid.
+    Repaid,
+    // The lender claimed the collateral, so the loan was deleted from the Cooler.
+    Claimed,
+}
+
+impl std::fmt::Display for LoanStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoanStatus::Active => write!(f, "Active"),
+            LoanStatus::Expired => write!(f, "Expired"),
+            LoanStatus::Repaid => write!(f, "Repaid"),
+            LoanStatus::Claimed => write!(f, "Claimed by lender"),
+        }
+    }
+}
+
+// Snapshot of a Cooler loan.
+#[derive(Clone, Debug)]
+pub struct LoanInfo {
+    pub principal: U256,
+    pub interest_due: U256,
+    pub collateral: U256,
+    pub expiry: i64,
+    pub lender: Address,
+    pub status: LoanStatus,
+}
+
+// Symbol and decimals of an ERC20 token, used to display loan amounts.
+#[derive(Clone, Debug)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl TokenInfo {
+    // Formats a raw token amount with the token decimals and symbol, e.g. "1500.25 DAI".
+    pub fn format_amount(&self, amount: U256) -> String {
+        let amount = format_units(amount, self.decimals as u32).unwrap_or(amount.to_string());
+        // Trim the trailing zeros left by the fixed number of decimals.
+        let amount = match amount.contains('.') {
+            true => amount.trim_end_matches('0').trim_end_matches('.').to_string(),
+            false => amount,
+        };
+        format!("{} {}", amount, self.symbol)
+    }
+}
+
+impl LoanInfo {
+    // Seconds until expiry, zero once the loan has expired.
+    pub fn time_left(&self) -> u64 {
+        (self.expiry - now()).max(0) as u64
+    }
+
+    // Seconds since expiry, zero while the loan is still running.
+    pub fn overdue(&self) -> u64 {
+        (now() - self.expiry).max(0) as u64
+    }
+}
+
+// Public method to read the current state of a loan from its Cooler contract.
+pub async fn get_loan(provider: Arc<Provider<Http>>, cooler: Address, loan_id: i64) -> Result<LoanInfo, ContractError<Provider<Http>>> {
+    let contract = ICooler::new(cooler, provider);
+    let (_request, principal, interest_due, collateral, expiry, lender, _recipient, _callback) =
+        contract.get_loan(U256::from(loan_id)).call().await?;
+
+    let expiry = expiry.min(U256::from(i64::MAX)).as_u64() as i64;
+    // Claimed loans are deleted from storage, and fully repaid ones keep their lender but owe nothing.
+    let status = if lender == Address::zero() {
+        LoanStatus::Claimed
+    } else if principal.is_zero() && interest_due.is_zero() {
+        LoanStatus::Repaid
+    } else if expiry <= now() {
+        LoanStatus::Expired
+    } else {
+        LoanStatus::Active
+    };
+
+    Ok(LoanInfo { principal, interest_due, collateral, expiry, lender, status })
+}
+
+// Most loans scanned when looking for the open loans of a Cooler.
+const MAX_LOANS_SCAN: i64 = 100;
+
+// Public method to list the loans of a Cooler that haven't been repaid nor claimed yet.
+pub async fn get_open_loans(provider: Arc<Provider<Http>>, cooler: Address) -> Result<Vec<(i64, LoanInfo)>, ContractError<Provider<Http>>> {
+    let mut loans = Vec::new();
+    for loan_id in 0..MAX_LOANS_SCAN {
+        match get_loan(provider.clone(), cooler, loan_id).await {
+            Ok(loan) => {
+                if matches!(loan.status, LoanStatus::Active | LoanStatus::Expired) {
+                    loans.push((loan_id, loan));
+                }
+            },
+            // Reading past the last loan of the Cooler reverts.
+            Err(error) if error.is_revert() => break,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(loans)
+}
+
+// Public method to read the collateral and debt tokens of a Cooler contract.
+pub async fn get_cooler_tokens(provider: Arc<Provider<Http>>, cooler: Address) -> Result<(TokenInfo, TokenInfo), ContractError<Provider<Http>>> {
+    let contract = ICooler::new(cooler, provider.clone());
+    let collateral = get_token(provider.clone(), contract.collateral().call().await?).await?;
+    let debt = get_token(provider, contract.debt().call().await?).await?;
+    Ok((collateral, debt))
+}
+
+async fn get_token(provider: Arc<Provider<Http>>, token: Address) -> Result<TokenInfo, ContractError<Provider<Http>>> {
+    let contract = IERC20::new(token, provider);
+    let symbol = contract.symbol().call().await?;
+    let decimals = contract.decimals().call().await?;
+    Ok(TokenInfo { symbol, decimals })
+}
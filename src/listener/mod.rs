@@ -1,11 +1,16 @@
-mod alerts;
-use alerts::send_webhook;
+pub mod alerts;
+pub mod loans;
+use alerts::{send_email, send_webhook, Notice};
+use loans::{get_loan, LoanStatus};
 use lettre::transport::smtp::authentication::Credentials;
-use crate::{registry::utils::{get_active_alerts, deactivate_alert}, listener::alerts::send_email};
+use crate::{
+    registry::{crypto::FieldCipher, error::RegistryResult, types::{Actor, AlertDB}, Registry},
+    duration::now,
+};
 
-use sqlx;
-use std::{sync::Arc, time::Duration};
-use tokio::time::sleep;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::sync::Notify;
+use tokio::time::{sleep, sleep_until, Instant};
 use ethers::{
     prelude::abigen,
     providers::{Http, Provider},
@@ -17,11 +22,35 @@ abigen!(
     "[function timeToExpiry(address cooler_, uint256 loanID_) external view returns (uint256 secondsToExpiry)]"
 );
 
-pub async fn monitor(contract_address: Address, provider: Arc<Provider<Http>>, database: &sqlx::SqlitePool, email_creds: Credentials) {
-    let contract = ICoolerMonitoring::new(contract_address, provider);
+// Longest time between two consecutive checks of the network state.
+const MAX_POLL_INTERVAL: u64 = 12 * 3600;
+// Shortest time between two consecutive checks, to avoid hammering the RPC near a deadline or when woken up often.
+const MIN_POLL_INTERVAL: u64 = 60;
+// Time before trying again to deliver a notification that couldn't be sent through any channel.
+const RETRY_INTERVAL: u64 = 15 * 60;
+// Delays after expiry at which post-expiry reminders are sent.
+const POST_EXPIRY_REMINDERS: [u64; 3] = [0, 3600, 6 * 3600];
+// Once the reminder series is over, keep reminding the user once a day.
+const POST_EXPIRY_REPEAT: u64 = 24 * 3600;
+
+// Seconds after expiry at which the post-expiry reminder with the given index is due.
+fn post_expiry_delay(reminder: usize) -> u64 {
+    match POST_EXPIRY_REMINDERS.get(reminder) {
+        Some(delay) => *delay,
+        None => {
+            let last = POST_EXPIRY_REMINDERS[POST_EXPIRY_REMINDERS.len() - 1];
+            last + (reminder + 1 - POST_EXPIRY_REMINDERS.len()) as u64 * POST_EXPIRY_REPEAT
+        }
+    }
+}
+
+// `wakeup` is notified by the bot when alerts are created or re-armed, as they may be due before the next planned check.
+pub async fn monitor(contract_address: Address, provider: Arc<Provider<Http>>, registry: &Registry, email_creds: Credentials, cipher: &FieldCipher, wakeup: &Notify) {
+    let contract = ICoolerMonitoring::new(contract_address, provider.clone());
     println!("\n\nMonitoring contract: {:?}", contract);
     loop {
-        let alerts = match get_active_alerts(database).await {
+        let poll_started = Instant::now();
+        let alerts = match registry.get_active_alerts().await {
             Ok(alerts) => alerts,
             Err(error) => {
                 println!("Error: {:?}", error);
@@ -29,69 +58,140 @@ pub async fn monitor(contract_address: Address, provider: Arc<Provider<Http>>, d
             }
         };
 
-        for i in 0..alerts.len() {
-            let cooler: Address = alerts[i].get_cooler().parse().unwrap();
-            let loan_id: U256 = U256::from(alerts[i].get_loan_id());
-            let threshold: U256 = U256::from(alerts[i].get_threshold() * 24 * 3600);
+        let mut next_poll = MAX_POLL_INTERVAL;
+        for alert in alerts.iter() {
+            let cooler = alert.get_cooler();
+
+            // Alerts past their first notification are following the loan after expiry.
+            if alert.get_stage() > 0 {
+                let loan = match get_loan(provider.clone(), cooler, alert.get_loan_id()).await {
+                    Ok(loan) => loan,
+                    Err(error) => {
+                        println!("Error: {:?}", error);
+                        continue;
+                    }
+                };
+                match loan.status {
+                    LoanStatus::Repaid | LoanStatus::Claimed => {
+                        // Nothing left to remind the user about.
+                        retry_update(|| registry.deactivate_alert(alert.get_alert_id(), Actor::LISTENER)).await;
+                    },
+                    LoanStatus::Active => {
+                        // The loan was extended, wait for it to expire.
+                        next_poll = next_poll.min(loan.expiry.saturating_sub(now()).max(0) as u64);
+                    },
+                    LoanStatus::Expired => {
+                        let overdue = loan.overdue();
+                        let due = post_expiry_delay(alert.get_stage() as usize - 1);
+                        if overdue < due {
+                            next_poll = next_poll.min(due - overdue);
+                            continue;
+                        }
+                        if !notify(alert, &Notice::Overdue { overdue, expiry: loan.expiry }, &email_creds, cipher).await {
+                            // Keep the reminder due, so that it is sent on a later check.
+                            next_poll = next_poll.min(RETRY_INTERVAL);
+                            continue;
+                        }
+                        let stage = alert.get_stage() + 1;
+                        retry_update(|| registry.set_alert_stage(alert.get_alert_id(), stage, Actor::LISTENER)).await;
+                        next_poll = next_poll.min(post_expiry_delay(stage as usize - 1).saturating_sub(overdue));
+                    },
+                }
+                continue;
+            }
+
+            let loan_id: U256 = U256::from(alert.get_loan_id());
+            let threshold: U256 = U256::from(alert.get_threshold());
             if let Ok(time_left) = contract.time_to_expiry(
                 cooler,
                 loan_id
             ).call().await {
                 // Check wether an alert should be sent or not.
-                if time_left <= threshold {
-                    // Send webhook alerts.
-                    match alerts[i].get_webhook_url() {
-                        Some(webhook_url) => {
-                            send_webhook(
-                                webhook_url,
-                                alerts[i].get_cooler(),
-                                alerts[i].get_loan_id(),
-                                time_left.as_u64() / 24 / 3600
-                            ).await;
-                            // Deactivate the alert after informing the user.
-                            match deactivate_alert(database, alerts[i].get_alert_id()).await {
-                                Ok(_) => (),
-                                Err(_) => {
-                                    // Try again after 1 seconds
-                                    sleep(Duration::from_secs(1)).await;
-                                    match deactivate_alert(database, alerts[i].get_alert_id()).await {
-                                        Ok(_) => (),
-                                        Err(error) => println!("Error: {:?}", error),
-                                    }
-                                }
-                            }
-                        },
-                        None => (),
-                    };
-                    // Send email alerts.
-                    match alerts[i].get_email() {
-                        Some(receiver) => {
-                            send_email(
-                                email_creds.clone(),
-                                receiver,
-                                alerts[i].get_cooler(),
-                                alerts[i].get_loan_id(),
-                                time_left.as_u64() / 24 / 3600
-                            ).await;
-                            // Deactivate the alert after informing the user.
-                            match deactivate_alert(database, alerts[i].get_alert_id()).await {
-                                Ok(_) => (),
-                                Err(_) => {
-                                    // Try again after 1 seconds
-                                    sleep(Duration::from_secs(1)).await;
-                                    match deactivate_alert(database, alerts[i].get_alert_id()).await {
-                                        Ok(_) => (),
-                                        Err(error) => println!("Error: {:?}", error),
-                                    }
-                                }
-                            }
-                        },
-                        None => (),
-                    }
+                if time_left > threshold {
+                    // Wake up in time for the threshold to be crossed.
+                    next_poll = next_poll.min((time_left - threshold).as_u64());
+                    continue;
+                }
+                let time_left = time_left.as_u64();
+                let expiry = now() + time_left as i64;
+                if !notify(alert, &Notice::Expiring { time_left, expiry }, &email_creds, cipher).await {
+                    // Leave the alert armed, so that it triggers again on a later check.
+                    next_poll = next_poll.min(RETRY_INTERVAL);
+                    continue;
+                }
+
+                if alert.has_post_expiry() {
+                    // Keep following the loan until it is repaid or claimed.
+                    retry_update(|| registry.set_alert_stage(alert.get_alert_id(), 1, Actor::LISTENER)).await;
+                    next_poll = next_poll.min(time_left);
+                } else {
+                    // Deactivate the alert after informing the user.
+                    retry_update(|| registry.deactivate_alert(alert.get_alert_id(), Actor::LISTENER)).await;
                 }
             }
         }
-        // Monitor network state every 12 hours.
-        sleep(Duration::from_secs(12*3600)).await;
+        // Paused and snoozed alerts are skipped, but must be checked as soon as they wake up.
+        match registry.get_next_resume_time().await {
+            Ok(Some(resume_time)) => next_poll = next_poll.min((resume_time - now()).max(0) as u64),
+            Ok(None) => (),
+            Err(error) => println!("Error: {:?}", error),
+        }
+        // Monitor network state every 12 hours, or earlier if an alert is about to be due or has just changed.
+        tokio::select! {
+            _ = sleep(Duration::from_secs(next_poll.max(MIN_POLL_INTERVAL))) => (),
+            _ = wakeup.notified() => {
+                // Changes coming in quick succession are checked together.
+                sleep_until(poll_started + Duration::from_secs(MIN_POLL_INTERVAL)).await;
+            },
+        }
+    }
+}
+
+// Sends the notice through every channel configured in the alert.
+// Receivers are decrypted here, right before delivery, and never stored in plaintext.
+// Returns whether the notice reached the user through at least one of them.
+async fn notify(alert: &AlertDB, notice: &Notice, email_creds: &Credentials, cipher: &FieldCipher) -> bool {
+    let mut delivered = false;
+    // Send webhook alerts.
+    if let Some(webhook_url) = alert.get_encrypted_webhook_url() {
+        let result = match cipher.open(webhook_url) {
+            Ok(webhook_url) => send_webhook(&webhook_url, notice, alert.get_cooler(), alert.get_loan_id()).await,
+            Err(error) => Err(error.into()),
+        };
+        match result {
+            Ok(_) => delivered = true,
+            Err(error) => println!("Could not execute webhook for alert {} of user {}: {:?}", alert.get_alert_id(), alert.get_user_id(), error),
+        }
+    }
+    // Send email alerts.
+    if let Some(receiver) = alert.get_encrypted_email() {
+        let result = match cipher.open(receiver) {
+            Ok(receiver) => send_email(email_creds.clone(), &receiver, notice, alert.get_cooler(), alert.get_loan_id()).await,
+            Err(error) => Err(error.into()),
+        };
+        match result {
+            Ok(_) => delivered = true,
+            Err(error) => println!("Could not send email for alert {} of user {}: {:?}", alert.get_alert_id(), alert.get_user_id(), error),
+        }
+    }
+    delivered
+}
+
+// Runs a registry update, trying again once if it failed for a reason that may go away.
+async fn retry_update<F, R>(update: F)
+where
+    F: Fn() -> R,
+    R: Future<Output = RegistryResult<()>>,
+{
+    match update().await {
+        Ok(_) => (),
+        Err(error) if error.is_transient() => {
+            // Try again after 1 seconds
+            sleep(Duration::from_secs(1)).await;
+            if let Err(error) = update().await {
+                println!("Error: {:?}", error);
+            }
+        },
+        Err(error) => println!("Error: {:?}", error),
     }
-}
\ No newline at end of file
+}
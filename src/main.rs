@@ -1,9 +1,10 @@
+mod address;
+mod cli;
 mod discord;
+mod duration;
 mod registry;
 mod listener;
 
-use sqlx;
-use tokio;
 use std::env;
 use std::error::Error;
 use std::sync::Arc;
@@ -18,15 +19,20 @@ use ethers::{
 };
 
 use discord::{GENERAL_GROUP, Bot};
+use registry::Registry;
+use registry::crypto::FieldCipher;
+use registry::retention::Retention;
+use discord::limits::Limits;
+use discord::registration::CommandScope;
 
 struct Config {
     provider: Arc<Provider<Http>>,
-    database: sqlx::SqlitePool,
+    registry: Registry,
     email_creds: Credentials,
 }
 
 impl Config {
-    pub async fn new(database: sqlx::SqlitePool) -> Self {
+    pub async fn new(registry: Registry) -> Self {
         // Configure RPC provider
         let network = std::env::var("NETWORK_RPC").expect("missing NETWORK_RPC");
         let provider: Arc<Provider<Http>> = Arc::new(Provider::try_from(network).expect("invalid NETWORK_RPC"));
@@ -36,11 +42,11 @@ impl Config {
         let email_pwd = std::env::var("EMAIL_PASSWORD").expect("missing EMAIL_PASSWORD");
         let email_creds = Credentials::new(email_usr, email_pwd);
 
-        Self { provider, database, email_creds }
+        Self { provider, registry, email_creds }
     }
 
-    pub fn get_db(&self) -> &sqlx::SqlitePool {
-        &self.database
+    pub fn get_registry(&self) -> &Registry {
+        &self.registry
     }
 
     pub fn get_provider(&self) -> Arc<Provider<Http>> {
@@ -57,35 +63,41 @@ impl Config {
 async fn main() -> Result<(), Box<dyn Error>> {
     // Load env variables
     dotenv::dotenv().ok();
+
+    // Run a maintenance command instead of the bot if one is given.
+    if let Some(command) = env::args().nth(1) {
+        return cli::run(&command, &env::args().skip(2).collect::<Vec<_>>()).await;
+    }
+
     let bot_token = env::var("DISCORD_TOKEN").expect("Expected a token in the .env file");
     let monitoring_address: Address = "0xA00F4b7c57a4995796D6E2ae4A6D5dEc8a557367".parse().unwrap();
 
-    // Initiate a connection to the database file, creating the file if required.
-    let database = sqlx::sqlite::SqlitePoolOptions::new()
-    .max_connections(5)
-    .connect_with(sqlx::sqlite::SqliteConnectOptions::new()
-            .filename("cooler-alerts.sqlite")
-            .create_if_missing(true),
-    )
-    .await
-    .expect("Couldn't connect to database");
-
-    // Update DB schema to the latest version.
-    sqlx::migrate!("./migrations").run(&database).await.expect("Couldn't run database migrations");
+    // Connect to the alerts database, updating its schema to the latest version.
+    let registry = registry::connect(&registry::database_url()).await.expect("Couldn't connect to database");
+    let cipher = Arc::new(FieldCipher::from_env());
+    // Seal the receivers left in plaintext by previous versions, or with a rotated key, before serving anyone.
+    cli::reencrypt(&registry, &cipher).await.expect("Couldn't encrypt the stored receivers");
 
     // Initialize the required system configuration.
-    let config: Config = Config::new(database.clone()).await;
+    let config: Config = Config::new(registry.clone()).await;
+    let provider = config.get_provider();
+    let email_creds = config.get_email_creds();
 
-    // New thread to monitor the chain.
-    tokio::spawn(async move {
-        listener::monitor(monitoring_address, config.get_provider(), config.get_db(), config.get_email_creds()).await;
-    });
+    // New thread to remove for good the alerts deleted longer ago than the retention window.
+    let retention = Retention::from_env();
+    tokio::spawn(registry::retention::purge_deleted_alerts(registry.clone(), retention));
 
     // Configure and initialize the Discord bot to manage alerts.
     let framework = StandardFramework::new()
         .configure(|c| c.prefix("~"))
         .group(&GENERAL_GROUP);
-    let bot = Bot::new(database.clone());
+    let bot = Bot::new(registry, cipher.clone(), provider, email_creds, CommandScope::from_env(), Limits::from_env(), retention);
+
+    // New thread to monitor the chain, woken up by the bot when alerts change.
+    let wakeup = bot.wakeup();
+    tokio::spawn(async move {
+        listener::monitor(monitoring_address, config.get_provider(), config.get_registry(), config.get_email_creds(), &cipher, &wakeup).await;
+    });
 
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
@@ -0,0 +1,224 @@
+use std::fmt;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+// Prefix of the encrypted values. Values without it were stored in plaintext before encryption was introduced,
+// and are read as they are until the bot seals them on startup.
+const SEALED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+// Webhook URLs are bearer credentials and emails are personal data, so both are encrypted before being stored.
+// Each value is sealed with AES-256-GCM and tagged with the ID of its key, so that keys can be rotated:
+// new values use the current key, while the old keys are only kept to read values sealed before the rotation.
+pub struct FieldCipher {
+    current: FieldKey,
+    previous: Vec<FieldKey>,
+}
+
+struct FieldKey {
+    id: String,
+    cipher: Aes256Gcm,
+    // Key of the blind index, derived from the encryption key.
+    index_key: Vec<u8>,
+}
+
+impl FieldKey {
+    fn new(key: &[u8; 32]) -> Self {
+        let digest = Sha256::digest(key);
+        let id = digest[..4].iter().map(|byte| format!("{byte:02x}")).collect();
+        let mut index_key = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        index_key.update(b"blind index");
+        Self { id, cipher: Aes256Gcm::new(key.into()), index_key: index_key.finalize().into_bytes().to_vec() }
+    }
+}
+
+// A value ready to be stored: the ciphertext, a keyed hash to compare values without decrypting them,
+// and a masked version that can be shown to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SealedField {
+    pub ciphertext: String,
+    pub blind_index: String,
+    pub hint: String,
+}
+
+impl SealedField {
+    // Splits an optional field into its optional columns.
+    pub fn columns(field: Option<SealedField>) -> (Option<String>, Option<String>, Option<String>) {
+        match field {
+            Some(field) => (Some(field.ciphertext), Some(field.blind_index), Some(field.hint)),
+            None => (None, None, None),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CryptoError {
+    // The value was sealed with a key that is no longer configured.
+    UnknownKey(String),
+    // The value was altered, or isn't a sealed value at all.
+    Corrupted,
+}
+
+impl std::error::Error for CryptoError {}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::UnknownKey(id) => write!(f, "The value was encrypted with the unknown key {id}."),
+            CryptoError::Corrupted => write!(f, "The encrypted value is corrupted."),
+        }
+    }
+}
+
+impl FieldCipher {
+    pub fn new(current: &[u8; 32], previous: &[[u8; 32]]) -> Self {
+        Self { current: FieldKey::new(current), previous: previous.iter().map(FieldKey::new).collect() }
+    }
+
+    // Reads the base64 encoded keys from the `ENCRYPTION_KEY` env variable and, while rotating keys,
+    // the comma separated `ENCRYPTION_OLD_KEYS`.
+    pub fn from_env() -> Self {
+        let current = parse_key(&std::env::var("ENCRYPTION_KEY").expect("missing ENCRYPTION_KEY"))
+            .expect("invalid ENCRYPTION_KEY, expected 32 bytes encoded in base64");
+        let previous: Vec<[u8; 32]> = std::env::var("ENCRYPTION_OLD_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| parse_key(key).expect("invalid ENCRYPTION_OLD_KEYS, expected 32 bytes keys encoded in base64"))
+            .collect();
+        Self::new(&current, &previous)
+    }
+
+    pub fn seal(&self, value: &str, hint: String) -> SealedField {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.current.cipher.encrypt(&nonce, value.as_bytes()).expect("AES-GCM encryption doesn't fail");
+        let sealed = [nonce.as_slice(), &ciphertext].concat();
+        SealedField {
+            ciphertext: format!("{SEALED_PREFIX}{}:{}", self.current.id, BASE64.encode(sealed)),
+            blind_index: self.blind_index(value),
+            hint,
+        }
+    }
+
+    pub fn seal_webhook_url(&self, webhook_url: &str) -> SealedField {
+        self.seal(webhook_url, mask_webhook_url(webhook_url))
+    }
+
+    pub fn seal_email(&self, email: &str) -> SealedField {
+        self.seal(email, mask_email(email))
+    }
+
+    // Stored value as it can leave the database: sealed values are kept, plaintext ones are sealed first.
+    pub fn ensure_sealed(&self, stored: &str) -> String {
+        match stored.starts_with(SEALED_PREFIX) {
+            true => stored.to_string(),
+            false => self.seal(stored, String::new()).ciphertext,
+        }
+    }
+
+    // Decrypts a stored value. Only meant to be called right before delivering a notification.
+    pub fn open(&self, stored: &str) -> Result<String, CryptoError> {
+        let Some(sealed) = stored.strip_prefix(SEALED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let (key_id, payload) = sealed.split_once(':').ok_or(CryptoError::Corrupted)?;
+        let key = std::iter::once(&self.current)
+            .chain(self.previous.iter())
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| CryptoError::UnknownKey(key_id.to_string()))?;
+        let payload = BASE64.decode(payload).map_err(|_| CryptoError::Corrupted)?;
+        if payload.len() < NONCE_LEN {
+            return Err(CryptoError::Corrupted);
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let value = key.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| CryptoError::Corrupted)?;
+        String::from_utf8(value).map_err(|_| CryptoError::Corrupted)
+    }
+
+    // Whether the stored value is sealed with the current key, so that re-encryption can skip it.
+    pub fn is_current(&self, stored: &str) -> bool {
+        stored.strip_prefix(SEALED_PREFIX).and_then(|sealed| sealed.split_once(':')).map(|(key_id, _)| key_id) == Some(&self.current.id)
+    }
+
+    // Keyed hash of a value, equal for equal values. Used to keep alerts unique without decrypting them.
+    pub fn blind_index(&self, value: &str) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.current.index_key).expect("HMAC accepts keys of any length");
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+fn parse_key(key: &str) -> Option<[u8; 32]> {
+    BASE64.decode(key.trim()).ok()?.try_into().ok()
+}
+
+// Keeps the webhook ID, which is public, and hides its token.
+pub fn mask_webhook_url(webhook_url: &str) -> String {
+    match webhook_url.split_once("/webhooks/").and_then(|(_, path)| path.split('/').next()) {
+        Some(webhook_id) if !webhook_id.is_empty() => format!("webhook {webhook_id}/••••"),
+        _ => "webhook ••••".to_string(),
+    }
+}
+
+// Keeps the first character and the domain of the address.
+pub fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => format!("{}•••@{domain}", local.chars().next().unwrap_or('•')),
+        None => "•••".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEBHOOK_URL: &str = "https://discord.com/api/webhooks/123/secret-token";
+
+    #[test]
+    fn sealed_values_can_only_be_read_with_their_key() {
+        let cipher = FieldCipher::new(&[1; 32], &[]);
+        let sealed = cipher.seal_webhook_url(WEBHOOK_URL);
+        assert!(!sealed.ciphertext.contains("secret-token"));
+        assert_eq!(sealed.hint, "webhook 123/••••");
+        assert_eq!(cipher.open(&sealed.ciphertext).unwrap(), WEBHOOK_URL);
+
+        let other = FieldCipher::new(&[2; 32], &[]);
+        assert!(matches!(other.open(&sealed.ciphertext), Err(CryptoError::UnknownKey(_))));
+        let (header, payload) = sealed.ciphertext.rsplit_once(':').unwrap();
+        let mut payload = BASE64.decode(payload).unwrap();
+        payload[NONCE_LEN] ^= 1;
+        assert_eq!(cipher.open(&format!("{header}:{}", BASE64.encode(payload))), Err(CryptoError::Corrupted));
+    }
+
+    #[test]
+    fn rotated_keys_still_open_old_values() {
+        let old = FieldCipher::new(&[1; 32], &[]);
+        let sealed = old.seal_email("cooler_alerts@yxz.com");
+        let rotated = FieldCipher::new(&[2; 32], &[[1; 32]]);
+        assert_eq!(rotated.open(&sealed.ciphertext).unwrap(), "cooler_alerts@yxz.com");
+        assert!(old.is_current(&sealed.ciphertext));
+        assert!(!rotated.is_current(&sealed.ciphertext));
+    }
+
+    #[test]
+    fn blind_index_matches_equal_values_only() {
+        let cipher = FieldCipher::new(&[1; 32], &[]);
+        let first = cipher.seal_email("cooler_alerts@yxz.com");
+        let second = cipher.seal_email("cooler_alerts@yxz.com");
+        assert_ne!(first.ciphertext, second.ciphertext);
+        assert_eq!(first.blind_index, second.blind_index);
+        assert_ne!(first.blind_index, cipher.blind_index("other@yxz.com"));
+    }
+
+    #[test]
+    fn legacy_plaintext_is_read_as_is() {
+        let cipher = FieldCipher::new(&[1; 32], &[]);
+        assert_eq!(cipher.open("cooler_alerts@yxz.com").unwrap(), "cooler_alerts@yxz.com");
+        assert!(!cipher.is_current("cooler_alerts@yxz.com"));
+        assert_eq!(mask_email("cooler_alerts@yxz.com"), "c•••@yxz.com");
+    }
+}
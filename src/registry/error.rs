@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fmt;
+
+// Errors returned by the registry, so that callers can react to each of them differently.
+#[derive(Debug)]
+pub enum RegistryError {
+    // The alert doesn't exist, or it belongs to another user.
+    NotFound,
+    // The entry conflicts with an existing one.
+    Duplicate,
+    // The input was rejected before reaching the database.
+    Validation(String),
+    // Creating the alert would go over the quota of the user or of the guild. `used` is the current number of alerts.
+    QuotaExceeded { scope: QuotaScope, used: i64, limit: i64 },
+    // Any other database failure.
+    Database(sqlx::Error),
+}
+
+// Who a quota applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaScope {
+    User,
+    Guild,
+}
+
+pub type RegistryResult<T> = Result<T, RegistryError>;
+
+impl RegistryError {
+    // Whether the same operation may succeed if tried again, e.g. a busy database or a lost connection.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RegistryError::Database(sqlx::Error::Database(error)) => {
+                // SQLITE_BUSY and SQLITE_LOCKED.
+                matches!(error.code().as_deref(), Some("5") | Some("6"))
+            },
+            RegistryError::Database(error) => matches!(
+                error,
+                sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl Error for RegistryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RegistryError::Database(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NotFound => write!(f, "No matching alert was found."),
+            RegistryError::Duplicate => write!(f, "An identical entry already exists."),
+            RegistryError::Validation(reason) => write!(f, "{reason}"),
+            RegistryError::QuotaExceeded { scope: QuotaScope::User, used, limit } => write!(f, "The user has {used} alerts out of {limit} allowed."),
+            RegistryError::QuotaExceeded { scope: QuotaScope::Guild, used, limit } => write!(f, "The guild has {used} alerts out of {limit} allowed."),
+            RegistryError::Database(error) => write!(f, "Database error: {error}"),
+        }
+    }
+}
+
+impl From<sqlx::Error> for RegistryError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => RegistryError::NotFound,
+            sqlx::Error::Database(ref database_error) if database_error.is_unique_violation() => RegistryError::Duplicate,
+            error => RegistryError::Database(error),
+        }
+    }
+}
+
+impl From<sqlx::migrate::MigrateError> for RegistryError {
+    fn from(error: sqlx::migrate::MigrateError) -> Self {
+        RegistryError::Database(sqlx::Error::Migrate(Box::new(error)))
+    }
+}
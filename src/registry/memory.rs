@@ -0,0 +1,404 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use async_trait::async_trait;
+use ethers::types::Address;
+use crate::address::canonical_address;
+use crate::duration::now;
+use crate::registry::crypto::SealedField;
+use crate::registry::{check_quota, created_detail, edited_detail, paused_event, validate_new_alert, AlertStore};
+use crate::registry::error::{QuotaScope, RegistryError, RegistryResult};
+use crate::registry::types::{SqlxAlertDB, Actor, AlertDB, AlertEvent, AlertFilter, AlertQuota, AlertUpdate, CreatedAlert, EventFilter, EventKind, NewAlert, RestoredAlerts, UserData, ForgottenUser};
+
+// Alerts kept in memory, lost on restart. Used by the tests and to try the bot without a database.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    // Alerts in creation order, like the rowid order of the SQL backends.
+    alerts: Vec<SqlxAlertDB>,
+    last_alert_id: i64,
+    events: Vec<AlertEvent>,
+    last_event_id: i64,
+    public_replies: HashMap<i64, bool>,
+    registered_commands: HashMap<String, String>,
+}
+
+impl MemoryState {
+    fn user_alert(&mut self, user_id: i64, alert_id: i64) -> RegistryResult<&mut SqlxAlertDB> {
+        self.alerts
+            .iter_mut()
+            .find(|alert| alert.alert_id == alert_id && alert.user_id == user_id && alert.deleted_at.is_none())
+            .ok_or(RegistryError::NotFound)
+    }
+
+    // Like `user_alert`, but also finds the deleted alerts that haven't been purged yet.
+    fn stored_user_alert(&mut self, user_id: i64, alert_id: i64) -> RegistryResult<&mut SqlxAlertDB> {
+        self.alerts
+            .iter_mut()
+            .find(|alert| alert.alert_id == alert_id && alert.user_id == user_id)
+            .ok_or(RegistryError::NotFound)
+    }
+
+    fn live_alerts(&self) -> impl Iterator<Item = &SqlxAlertDB> {
+        self.alerts.iter().filter(|alert| alert.deleted_at.is_none())
+    }
+
+    fn alert(&mut self, alert_id: i64) -> Option<&mut SqlxAlertDB> {
+        self.alerts.iter_mut().find(|alert| alert.alert_id == alert_id)
+    }
+
+    // Alert of the user for the same loan and destination, which must stay unique. Receivers are compared by their hash.
+    fn same_destination(&self, user_id: i64, cooler: &str, loan_id: i64, webhook_hash: Option<&str>, email_hash: Option<&str>) -> Option<i64> {
+        self.live_alerts()
+            .find(|alert| {
+                alert.user_id == user_id
+                    && alert.cooler == cooler
+                    && alert.loan_id == loan_id
+                    && alert.webhook_hash.as_deref() == webhook_hash
+                    && alert.email_hash.as_deref() == email_hash
+            })
+            .map(|alert| alert.alert_id)
+    }
+
+    // Appends an entry to the history of an alert. Purges must be recorded before the alert is removed.
+    fn record_event(&mut self, alert_id: i64, actor: Actor, kind: EventKind, detail: Option<String>) {
+        let Some(alert) = self.alerts.iter().find(|alert| alert.alert_id == alert_id) else {
+            return;
+        };
+        self.last_event_id += 1;
+        self.events.push(AlertEvent {
+            event_id: self.last_event_id,
+            alert_id,
+            user_id: alert.user_id,
+            guild_id: alert.guild_id,
+            cooler: alert.cooler.clone(),
+            loan_id: alert.loan_id,
+            actor_id: actor.user_id,
+            source: actor.source.as_str().to_string(),
+            kind: kind.as_str().to_string(),
+            detail,
+            created_at: now(),
+        });
+    }
+
+    fn filtered(&self, user_id: i64, filter: &AlertFilter) -> impl Iterator<Item = &SqlxAlertDB> {
+        let filter = filter.clone();
+        self.live_alerts().filter(move |alert| {
+            alert.user_id == user_id
+                && filter.cooler.as_ref().is_none_or(|cooler| alert.cooler == canonical_address(cooler))
+                && filter.active.is_none_or(|active| alert.active == active)
+        })
+    }
+}
+
+impl MemoryStore {
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl AlertStore for MemoryStore {
+    async fn create_alert(&self, alert: NewAlert, quota: &AlertQuota, actor: Actor) -> RegistryResult<CreatedAlert> {
+        validate_new_alert(&alert)?;
+        let detail = created_detail(&alert);
+        let (webhook_url, webhook_hash, webhook_hint) = SealedField::columns(alert.webhook_url);
+        let (email, email_hash, email_hint) = SealedField::columns(alert.email);
+        let mut state = self.state();
+        let existing = state.same_destination(alert.user_id, &canonical_address(&alert.cooler), alert.loan_id, webhook_hash.as_deref(), email_hash.as_deref());
+        if let Some(alert_id) = existing {
+            let existing = state.user_alert(alert.user_id, alert_id)?;
+            existing.threshold = alert.threshold;
+            existing.post_expiry = alert.post_expiry;
+            existing.active = true;
+            existing.stage = 0;
+            state.record_event(alert_id, actor, EventKind::Rearmed, detail.map(|detail| format!("created again, {detail}")));
+            return Ok(CreatedAlert::Updated(alert_id));
+        }
+
+        let user_alerts = state.live_alerts().filter(|existing| existing.user_id == alert.user_id).count();
+        check_quota(QuotaScope::User, user_alerts as i64, quota.per_user)?;
+        if let Some(guild_id) = alert.guild_id {
+            let guild_alerts = state.live_alerts().filter(|existing| existing.guild_id == Some(guild_id)).count();
+            check_quota(QuotaScope::Guild, guild_alerts as i64, quota.per_guild)?;
+        }
+
+        state.last_alert_id += 1;
+        let alert_id = state.last_alert_id;
+        state.alerts.push(SqlxAlertDB {
+            alert_id,
+            user_id: alert.user_id,
+            cooler: canonical_address(&alert.cooler),
+            loan_id: alert.loan_id,
+            threshold: alert.threshold,
+            webhook_url,
+            email,
+            active: true,
+            post_expiry: alert.post_expiry,
+            stage: 0,
+            paused_until: None,
+            guild_id: alert.guild_id,
+            webhook_hash,
+            email_hash,
+            webhook_hint,
+            email_hint,
+            deleted_at: None,
+        });
+        state.record_event(alert_id, actor, EventKind::Created, detail);
+        Ok(CreatedAlert::New(alert_id))
+    }
+
+    async fn deactivate_alert(&self, alert_id: i64, actor: Actor) -> RegistryResult<()> {
+        let mut state = self.state();
+        if let Some(alert) = state.alert(alert_id) {
+            alert.active = false;
+        }
+        state.record_event(alert_id, actor, EventKind::Triggered, None);
+        Ok(())
+    }
+
+    async fn update_alert(&self, user_id: i64, alert_id: i64, update: AlertUpdate, actor: Actor) -> RegistryResult<()> {
+        let detail = edited_detail(&update);
+        let AlertUpdate { threshold, webhook_url, email, rearm } = update;
+        let (webhook_url, webhook_hash, webhook_hint) = SealedField::columns(webhook_url);
+        let (email, email_hash, email_hint) = SealedField::columns(email);
+        let mut state = self.state();
+        let alert = state.stored_user_alert(user_id, alert_id)?.clone();
+        let webhook_hash = webhook_hash.or(alert.webhook_hash);
+        let email_hash = email_hash.or(alert.email_hash);
+        let existing = state.same_destination(user_id, &alert.cooler, alert.loan_id, webhook_hash.as_deref(), email_hash.as_deref());
+        if existing.is_some_and(|existing| existing != alert_id) {
+            return Err(RegistryError::Duplicate);
+        }
+
+        let alert = state.stored_user_alert(user_id, alert_id)?;
+        alert.threshold = threshold.unwrap_or(alert.threshold);
+        alert.webhook_url = webhook_url.or(alert.webhook_url.take());
+        alert.webhook_hash = webhook_hash;
+        alert.webhook_hint = webhook_hint.or(alert.webhook_hint.take());
+        alert.email = email.or(alert.email.take());
+        alert.email_hash = email_hash;
+        alert.email_hint = email_hint.or(alert.email_hint.take());
+        let rearm = rearm && (!alert.active || alert.stage > 0);
+        if rearm {
+            alert.active = true;
+            alert.stage = 0;
+        }
+        if detail.is_some() {
+            state.record_event(alert_id, actor, EventKind::Edited, detail);
+        }
+        if rearm {
+            state.record_event(alert_id, actor, EventKind::Rearmed, None);
+        }
+        Ok(())
+    }
+
+    async fn set_alert_paused_until(&self, user_id: i64, alert_id: i64, paused_until: Option<i64>, actor: Actor) -> RegistryResult<()> {
+        let mut state = self.state();
+        state.user_alert(user_id, alert_id)?.paused_until = paused_until;
+        let (kind, detail) = paused_event(paused_until);
+        state.record_event(alert_id, actor, kind, detail);
+        Ok(())
+    }
+
+    async fn set_alert_stage(&self, alert_id: i64, stage: i64, actor: Actor) -> RegistryResult<()> {
+        let mut state = self.state();
+        if let Some(alert) = state.alert(alert_id) {
+            alert.stage = stage;
+        }
+        state.record_event(alert_id, actor, EventKind::Triggered, Some(format!("stage {stage}")));
+        Ok(())
+    }
+
+    async fn delete_user_alerts_by_cooler(&self, user_id: i64, cooler: Address, loan_id: Option<i64>, actor: Actor) -> RegistryResult<u64> {
+        let filter = AlertFilter { cooler: Some(cooler), active: None };
+        let mut state = self.state();
+        let alert_ids: Vec<i64> = state
+            .filtered(user_id, &filter)
+            .filter(|alert| loan_id.is_none_or(|loan_id| alert.loan_id == loan_id))
+            .map(|alert| alert.alert_id)
+            .collect();
+        if alert_ids.is_empty() {
+            return Err(RegistryError::NotFound);
+        }
+        let now = now();
+        for alert_id in alert_ids.iter() {
+            state.user_alert(user_id, *alert_id)?.deleted_at = Some(now);
+            state.record_event(*alert_id, actor, EventKind::Deleted, None);
+        }
+        Ok(alert_ids.len() as u64)
+    }
+
+    async fn delete_alert(&self, user_id: i64, alert_id: i64, actor: Actor) -> RegistryResult<()> {
+        let mut state = self.state();
+        state.user_alert(user_id, alert_id)?.deleted_at = Some(now());
+        state.record_event(alert_id, actor, EventKind::Deleted, None);
+        Ok(())
+    }
+
+    async fn restore_alerts(&self, user_id: i64, cooler: Option<Address>, loan_id: Option<i64>, quota: &AlertQuota, actor: Actor) -> RegistryResult<RestoredAlerts> {
+        let cooler = cooler.as_ref().map(canonical_address);
+        let mut state = self.state();
+        let deleted: Vec<SqlxAlertDB> = state
+            .alerts
+            .iter()
+            .filter(|alert| {
+                alert.user_id == user_id
+                    && alert.deleted_at.is_some()
+                    && cooler.as_ref().is_none_or(|cooler| alert.cooler == *cooler)
+                    && loan_id.is_none_or(|loan_id| alert.loan_id == loan_id)
+            })
+            .cloned()
+            .collect();
+
+        let mut outcome = RestoredAlerts::default();
+        for alert in deleted {
+            if state.same_destination(user_id, &alert.cooler, alert.loan_id, alert.webhook_hash.as_deref(), alert.email_hash.as_deref()).is_some() {
+                outcome.duplicates += 1;
+                continue;
+            }
+            let user_alerts = state.live_alerts().filter(|existing| existing.user_id == user_id).count() as i64;
+            let guild_alerts = alert.guild_id.map(|guild_id| state.live_alerts().filter(|existing| existing.guild_id == Some(guild_id)).count() as i64);
+            if user_alerts >= quota.per_user || guild_alerts.is_some_and(|guild_alerts| guild_alerts >= quota.per_guild) {
+                outcome.over_quota += 1;
+                continue;
+            }
+
+            state.stored_user_alert(user_id, alert.alert_id)?.deleted_at = None;
+            state.record_event(alert.alert_id, actor, EventKind::Restored, None);
+            outcome.restored += 1;
+        }
+        Ok(outcome)
+    }
+
+    async fn purge_deleted_alerts(&self, deleted_before: i64, actor: Actor) -> RegistryResult<u64> {
+        let mut state = self.state();
+        let alert_ids: Vec<i64> = state
+            .alerts
+            .iter()
+            .filter(|alert| alert.deleted_at.is_some_and(|deleted_at| deleted_at < deleted_before))
+            .map(|alert| alert.alert_id)
+            .collect();
+        for alert_id in alert_ids.iter() {
+            state.record_event(*alert_id, actor, EventKind::Purged, None);
+        }
+        state.alerts.retain(|alert| !alert_ids.contains(&alert.alert_id));
+        Ok(alert_ids.len() as u64)
+    }
+
+    async fn get_user_data(&self, user_id: i64) -> RegistryResult<UserData> {
+        let state = self.state();
+        let alerts: Vec<&SqlxAlertDB> = state.alerts.iter().filter(|alert| alert.user_id == user_id).collect();
+        let distinct = |values: Vec<Option<String>>| values.into_iter().flatten().collect::<HashSet<_>>().len() as i64;
+        Ok(UserData {
+            alerts: alerts.iter().filter(|alert| alert.deleted_at.is_none()).count() as i64,
+            deleted_alerts: alerts.iter().filter(|alert| alert.deleted_at.is_some()).count() as i64,
+            webhooks: distinct(alerts.iter().map(|alert| alert.webhook_hash.clone()).collect()),
+            emails: distinct(alerts.iter().map(|alert| alert.email_hash.clone()).collect()),
+            guilds: distinct(alerts.iter().map(|alert| alert.guild_id.map(|guild_id| guild_id.to_string())).collect()),
+            events: state.events.iter().filter(|event| event.user_id == user_id || event.actor_id == Some(user_id)).count() as i64,
+        })
+    }
+
+    async fn forget_user(&self, user_id: i64) -> RegistryResult<ForgottenUser> {
+        let mut state = self.state();
+        let (alerts, events) = (state.alerts.len(), state.events.len());
+        state.alerts.retain(|alert| alert.user_id != user_id);
+        state.events.retain(|event| event.user_id != user_id && event.actor_id != Some(user_id));
+        Ok(ForgottenUser { alerts: (alerts - state.alerts.len()) as u64, events: (events - state.events.len()) as u64 })
+    }
+
+    async fn get_alert_events(&self, filter: &EventFilter, limit: i64) -> RegistryResult<Vec<AlertEvent>> {
+        Ok(self.state()
+            .events
+            .iter()
+            .rev()
+            .filter(|event| {
+                filter.alert_id.is_none_or(|alert_id| event.alert_id == alert_id)
+                    && filter.user_id.is_none_or(|user_id| event.user_id == user_id)
+                    && filter.guild_id.is_none_or(|guild_id| event.guild_id == Some(guild_id))
+            })
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_user_alerts(&self, user_id: i64, filter: &AlertFilter, limit: i64, offset: i64) -> RegistryResult<Vec<AlertDB>> {
+        Ok(self.state()
+            .filtered(user_id, filter)
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|alert| alert.clone().into())
+            .collect())
+    }
+
+    async fn get_user_coolers(&self, user_id: i64) -> RegistryResult<Vec<Address>> {
+        let mut coolers: Vec<Address> = self.state()
+            .filtered(user_id, &AlertFilter::default())
+            .filter_map(|alert| alert.cooler.parse().ok())
+            .collect();
+        coolers.sort();
+        coolers.dedup();
+        Ok(coolers)
+    }
+
+    async fn get_user_loan_ids(&self, user_id: i64, cooler: Address) -> RegistryResult<Vec<i64>> {
+        let filter = AlertFilter { cooler: Some(cooler), active: None };
+        let mut loan_ids: Vec<i64> = self.state().filtered(user_id, &filter).map(|alert| alert.loan_id).collect();
+        loan_ids.sort();
+        loan_ids.dedup();
+        Ok(loan_ids)
+    }
+
+    async fn count_filtered_user_alerts(&self, user_id: i64, filter: &AlertFilter) -> RegistryResult<i64> {
+        Ok(self.state().filtered(user_id, filter).count() as i64)
+    }
+
+    async fn get_user_alert(&self, user_id: i64, alert_id: i64) -> RegistryResult<AlertDB> {
+        Ok(self.state().user_alert(user_id, alert_id)?.clone().into())
+    }
+
+    async fn get_all_alerts(&self) -> RegistryResult<Vec<AlertDB>> {
+        Ok(self.state().alerts.iter().map(|alert| alert.clone().into()).collect())
+    }
+
+    async fn get_active_alerts(&self) -> RegistryResult<Vec<AlertDB>> {
+        let now = now();
+        Ok(self.state()
+            .live_alerts()
+            .filter(|alert| alert.active && alert.paused_until.is_none_or(|paused_until| paused_until <= now))
+            .map(|alert| alert.clone().into())
+            .collect())
+    }
+
+    async fn get_next_resume_time(&self) -> RegistryResult<Option<i64>> {
+        let now = now();
+        Ok(self.state()
+            .live_alerts()
+            .filter(|alert| alert.active)
+            .filter_map(|alert| alert.paused_until)
+            .filter(|paused_until| *paused_until > now)
+            .min())
+    }
+
+    async fn get_public_replies(&self, guild_id: i64) -> RegistryResult<bool> {
+        Ok(self.state().public_replies.get(&guild_id).copied().unwrap_or(false))
+    }
+
+    async fn set_public_replies(&self, guild_id: i64, public_replies: bool) -> RegistryResult<()> {
+        self.state().public_replies.insert(guild_id, public_replies);
+        Ok(())
+    }
+
+    async fn get_registered_commands(&self, scope: &str) -> RegistryResult<Option<String>> {
+        Ok(self.state().registered_commands.get(scope).cloned())
+    }
+
+    async fn set_registered_commands(&self, scope: &str, definition: &str) -> RegistryResult<()> {
+        self.state().registered_commands.insert(scope.to_string(), definition.to_string());
+        Ok(())
+    }
+}
@@ -1,2 +1,167 @@
+pub mod crypto;
+pub mod error;
+pub mod memory;
+pub mod postgres;
+pub mod retention;
+pub mod sqlite;
 pub mod types;
-pub mod utils;
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+use ethers::types::Address;
+use async_trait::async_trait;
+use error::{QuotaScope, RegistryError, RegistryResult};
+use types::{Actor, AlertDB, AlertEvent, AlertFilter, AlertQuota, AlertUpdate, CreatedAlert, EventFilter, EventKind, NewAlert, RestoredAlerts, UserData, ForgottenUser, PAUSED_INDEFINITELY};
+use crate::duration::{format_duration, format_timestamp};
+
+// Storage of the alerts and of the few bot settings. The listener and the Discord commands only rely on
+// this trait, so the backend can be picked at startup from the database URL.
+// Every change to an alert is recorded in its history, in the same transaction as the change itself.
+#[async_trait]
+pub trait AlertStore: Send + Sync {
+    // Stores a new alert, returning its ID. At least one receiver is required.
+    // If the user already has an alert for the same loan and destination, its threshold is updated instead.
+    // New alerts are rejected when the user or the guild already reached the quota.
+    async fn create_alert(&self, alert: NewAlert, quota: &AlertQuota, actor: Actor) -> RegistryResult<CreatedAlert>;
+
+    async fn deactivate_alert(&self, alert_id: i64, actor: Actor) -> RegistryResult<()>;
+
+    // Updates the given fields of an alert owned by the user. Deleted alerts are updated too,
+    // so that maintenance such as re-encryption covers them until they are purged.
+    async fn update_alert(&self, user_id: i64, alert_id: i64, update: AlertUpdate, actor: Actor) -> RegistryResult<()>;
+
+    // Mutes an alert owned by the user until the given timestamp, or unmutes it if `None`.
+    async fn set_alert_paused_until(&self, user_id: i64, alert_id: i64, paused_until: Option<i64>, actor: Actor) -> RegistryResult<()>;
+
+    async fn set_alert_stage(&self, alert_id: i64, stage: i64, actor: Actor) -> RegistryResult<()>;
+
+    // Deletes the alerts of the user for the given Cooler, or only those of one of its loans.
+    // Returns the number of deleted alerts.
+    // Deleted alerts are only marked as such, and are ignored everywhere else until they are restored or purged.
+    async fn delete_user_alerts_by_cooler(&self, user_id: i64, cooler: Address, loan_id: Option<i64>, actor: Actor) -> RegistryResult<u64>;
+
+    // Deletes an alert owned by the user.
+    async fn delete_alert(&self, user_id: i64, alert_id: i64, actor: Actor) -> RegistryResult<()>;
+
+    // Brings back the deleted alerts of the user, optionally only those of a Cooler or of one of its loans.
+    // Alerts the user created again since then, or that no longer fit in the quota, stay deleted.
+    async fn restore_alerts(&self, user_id: i64, cooler: Option<Address>, loan_id: Option<i64>, quota: &AlertQuota, actor: Actor) -> RegistryResult<RestoredAlerts>;
+
+    // Removes for good the alerts deleted before the given timestamp. Returns the number of purged alerts.
+    async fn purge_deleted_alerts(&self, deleted_before: i64, actor: Actor) -> RegistryResult<u64>;
+
+    // Summary of everything stored about the user, including the deleted alerts and the history.
+    async fn get_user_data(&self, user_id: i64) -> RegistryResult<UserData>;
+
+    // Erases everything stored about the user: their alerts, deleted or not, and the history of their alerts
+    // and of their actions. Nothing is recorded, so that no trace of the user is left.
+    async fn forget_user(&self, user_id: i64) -> RegistryResult<ForgottenUser>;
+
+    // Most recent entries of the history of the alerts matching the filter, newest first.
+    async fn get_alert_events(&self, filter: &EventFilter, limit: i64) -> RegistryResult<Vec<AlertEvent>>;
+
+    // One page of the alerts of a user, matching the given filter.
+    async fn get_user_alerts(&self, user_id: i64, filter: &AlertFilter, limit: i64, offset: i64) -> RegistryResult<Vec<AlertDB>>;
+
+    // Coolers the user has alerts for, used to suggest addresses.
+    async fn get_user_coolers(&self, user_id: i64) -> RegistryResult<Vec<Address>>;
+
+    // Loans of a Cooler the user has alerts for, used to suggest loan IDs.
+    async fn get_user_loan_ids(&self, user_id: i64, cooler: Address) -> RegistryResult<Vec<i64>>;
+
+    async fn count_filtered_user_alerts(&self, user_id: i64, filter: &AlertFilter) -> RegistryResult<i64>;
+
+    // An alert owned by the user. Alerts of other users are reported as not found.
+    async fn get_user_alert(&self, user_id: i64, alert_id: i64) -> RegistryResult<AlertDB>;
+
+    // Every stored alert, including the deleted ones not purged yet, used by the maintenance commands.
+    async fn get_all_alerts(&self) -> RegistryResult<Vec<AlertDB>>;
+
+    // Active alerts that are not paused nor snoozed.
+    async fn get_active_alerts(&self) -> RegistryResult<Vec<AlertDB>>;
+
+    // Earliest time at which a paused or snoozed active alert wakes up, if any.
+    async fn get_next_resume_time(&self) -> RegistryResult<Option<i64>>;
+
+    // Whether the bot replies to alert commands publicly in the guild. Replies are private by default.
+    async fn get_public_replies(&self, guild_id: i64) -> RegistryResult<bool>;
+
+    async fn set_public_replies(&self, guild_id: i64, public_replies: bool) -> RegistryResult<()>;
+
+    // Slash command definitions last registered in the given scope, if any.
+    async fn get_registered_commands(&self, scope: &str) -> RegistryResult<Option<String>>;
+
+    async fn set_registered_commands(&self, scope: &str, definition: &str) -> RegistryResult<()>;
+}
+
+// Shared handle to the alert store used by the whole bot.
+pub type Registry = Arc<dyn AlertStore>;
+
+// Database URL from the `DATABASE_URL` env variable, defaulting to a SQLite file in the working directory.
+pub fn database_url() -> String {
+    std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:cooler-alerts.sqlite".to_string())
+}
+
+// Opens the store matching the database URL, running its migrations:
+// `sqlite:<file>` for a SQLite file, `postgres://...` for Postgres, or `memory:` for a store that is lost on restart.
+pub async fn connect(database_url: &str) -> RegistryResult<Registry> {
+    match database_url.split(':').next() {
+        Some("sqlite") => Ok(Arc::new(sqlite::SqliteStore::connect(database_url).await?)),
+        Some("postgres") | Some("postgresql") => Ok(Arc::new(postgres::PostgresStore::connect(database_url).await?)),
+        Some("memory") => Ok(Arc::new(memory::MemoryStore::default())),
+        _ => Err(RegistryError::Validation(format!("Unsupported database URL: {database_url}"))),
+    }
+}
+
+fn validate_new_alert(alert: &NewAlert) -> RegistryResult<()> {
+    match alert.webhook_url.is_none() && alert.email.is_none() {
+        true => Err(RegistryError::Validation("An alert needs a webhook URL or an email.".to_string())),
+        false => Ok(()),
+    }
+}
+
+// Rejects a new alert once `used` alerts already reached the limit.
+fn check_quota(scope: QuotaScope, used: i64, limit: i64) -> RegistryResult<()> {
+    match used >= limit {
+        true => Err(RegistryError::QuotaExceeded { scope, used, limit }),
+        false => Ok(()),
+    }
+}
+
+// Updates and deletes filtered by owner don't touch any row when the user has no such alert.
+fn found(rows_affected: u64) -> RegistryResult<()> {
+    match rows_affected {
+        0 => Err(RegistryError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+// Detail recorded when an alert is created, or created again.
+fn created_detail(alert: &NewAlert) -> Option<String> {
+    Some(format!("threshold {}", format_duration(alert.threshold as u64)))
+}
+
+// Detail recorded when an alert is edited: the fields that changed, if any.
+fn edited_detail(update: &AlertUpdate) -> Option<String> {
+    let mut fields = Vec::new();
+    if let Some(threshold) = update.threshold {
+        fields.push(format!("threshold {}", format_duration(threshold as u64)));
+    }
+    if update.webhook_url.is_some() {
+        fields.push("webhook".to_string());
+    }
+    if update.email.is_some() {
+        fields.push("email".to_string());
+    }
+    (!fields.is_empty()).then(|| fields.join(", "))
+}
+
+// Event recorded when an alert is paused, snoozed or resumed.
+fn paused_event(paused_until: Option<i64>) -> (EventKind, Option<String>) {
+    match paused_until {
+        None => (EventKind::Resumed, None),
+        Some(PAUSED_INDEFINITELY) => (EventKind::Paused, None),
+        Some(paused_until) => (EventKind::Paused, Some(format!("until {}", format_timestamp(paused_until)))),
+    }
+}
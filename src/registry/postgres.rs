@@ -0,0 +1,478 @@
+use async_trait::async_trait;
+use ethers::types::Address;
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions};
+use crate::address::canonical_address;
+use crate::duration::now;
+use crate::registry::crypto::SealedField;
+use crate::registry::{check_quota, created_detail, edited_detail, found, paused_event, validate_new_alert, AlertStore};
+use crate::registry::error::{QuotaScope, RegistryResult};
+use crate::registry::types::{SqlxAlertDB, Actor, AlertDB, AlertEvent, AlertFilter, AlertQuota, AlertUpdate, CreatedAlert, EventFilter, EventKind, NewAlert, RestoredAlerts, UserData, ForgottenUser};
+
+// Alerts stored in Postgres, for hosted deployments.
+// The query macros are checked against the SQLite schema at build time, so queries here are checked at runtime.
+pub struct PostgresStore {
+    database: PgPool,
+}
+
+impl PostgresStore {
+    // Connects to the database and updates its schema to the latest version.
+    pub async fn connect(database_url: &str) -> RegistryResult<Self> {
+        let database = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+        sqlx::migrate!("./migrations_postgres").run(&database).await?;
+        Ok(Self { database })
+    }
+
+    // Empties every table, so that each test starts from a blank database.
+    #[cfg(test)]
+    pub async fn truncate(&self) -> RegistryResult<()> {
+        sqlx::query("TRUNCATE alerts, alert_events, guild_settings, command_registrations RESTART IDENTITY")
+            .execute(&self.database)
+            .await?;
+        Ok(())
+    }
+}
+
+// Appends an entry to the history of an alert, within the transaction of the change.
+// The alert is copied from its row, so deletions must be recorded before the row is gone.
+async fn record_event(connection: &mut PgConnection, alert_id: i64, actor: Actor, kind: EventKind, detail: Option<String>) -> RegistryResult<()> {
+    sqlx::query(
+        "INSERT INTO alert_events (alert_id, user_id, guild_id, cooler, loan_id, actor_id, source, kind, detail, created_at)
+        SELECT alert_id, user_id, guild_id, cooler, loan_id, $1, $2, $3, $4, $5 FROM alerts WHERE alert_id = $6",
+    )
+    .bind(actor.user_id)
+    .bind(actor.source.as_str())
+    .bind(kind.as_str())
+    .bind(detail)
+    .bind(now())
+    .bind(alert_id)
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl AlertStore for PostgresStore {
+    async fn create_alert(&self, alert: NewAlert, quota: &AlertQuota, actor: Actor) -> RegistryResult<CreatedAlert> {
+        validate_new_alert(&alert)?;
+        let detail = created_detail(&alert);
+        let (webhook_url, webhook_hash, webhook_hint) = SealedField::columns(alert.webhook_url);
+        let (email, email_hash, email_hint) = SealedField::columns(alert.email);
+        let cooler = canonical_address(&alert.cooler);
+        let mut transaction = self.database.begin().await?;
+        let existing: Option<i64> = sqlx::query_scalar(
+            "SELECT alert_id FROM alerts WHERE user_id = $1 AND cooler = $2 AND loan_id = $3 AND COALESCE(webhook_hash, '') = COALESCE($4, '') AND COALESCE(email_hash, '') = COALESCE($5, '') AND deleted_at IS NULL",
+        )
+        .bind(alert.user_id)
+        .bind(&cooler)
+        .bind(alert.loan_id)
+        .bind(&webhook_hash)
+        .bind(&email_hash)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        let created = match existing {
+            Some(alert_id) => {
+                sqlx::query("UPDATE alerts SET threshold = $1, post_expiry = $2, active = TRUE, stage = 0 WHERE alert_id = $3")
+                    .bind(alert.threshold)
+                    .bind(alert.post_expiry)
+                    .bind(alert_id)
+                    .execute(&mut *transaction)
+                    .await?;
+                let detail = detail.map(|detail| format!("created again, {detail}"));
+                record_event(&mut transaction, alert_id, actor, EventKind::Rearmed, detail).await?;
+                CreatedAlert::Updated(alert_id)
+            },
+            None => {
+                let user_alerts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alerts WHERE user_id = $1 AND deleted_at IS NULL")
+                    .bind(alert.user_id)
+                    .fetch_one(&mut *transaction)
+                    .await?;
+                check_quota(QuotaScope::User, user_alerts, quota.per_user)?;
+                if let Some(guild_id) = alert.guild_id {
+                    let guild_alerts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alerts WHERE guild_id = $1 AND deleted_at IS NULL")
+                        .bind(guild_id)
+                        .fetch_one(&mut *transaction)
+                        .await?;
+                    check_quota(QuotaScope::Guild, guild_alerts, quota.per_guild)?;
+                }
+
+                let alert_id = sqlx::query_scalar(
+                    "INSERT INTO alerts (user_id, cooler, loan_id, threshold, post_expiry, webhook_url, email, active, guild_id, webhook_hash, email_hash, webhook_hint, email_hint)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, TRUE, $8, $9, $10, $11, $12) RETURNING alert_id",
+                )
+                .bind(alert.user_id)
+                .bind(cooler)
+                .bind(alert.loan_id)
+                .bind(alert.threshold)
+                .bind(alert.post_expiry)
+                .bind(webhook_url)
+                .bind(email)
+                .bind(alert.guild_id)
+                .bind(webhook_hash)
+                .bind(email_hash)
+                .bind(webhook_hint)
+                .bind(email_hint)
+                .fetch_one(&mut *transaction)
+                .await?;
+                record_event(&mut transaction, alert_id, actor, EventKind::Created, detail).await?;
+                CreatedAlert::New(alert_id)
+            },
+        };
+        transaction.commit().await?;
+        Ok(created)
+    }
+
+    async fn deactivate_alert(&self, alert_id: i64, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        sqlx::query("UPDATE alerts SET active = FALSE WHERE alert_id = $1")
+            .bind(alert_id)
+            .execute(&mut *transaction)
+            .await?;
+        record_event(&mut transaction, alert_id, actor, EventKind::Triggered, None).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn update_alert(&self, user_id: i64, alert_id: i64, update: AlertUpdate, actor: Actor) -> RegistryResult<()> {
+        let detail = edited_detail(&update);
+        let AlertUpdate { threshold, webhook_url, email, rearm } = update;
+        let (webhook_url, webhook_hash, webhook_hint) = SealedField::columns(webhook_url);
+        let (email, email_hash, email_hint) = SealedField::columns(email);
+        let mut transaction = self.database.begin().await?;
+        let result = sqlx::query(
+            "UPDATE alerts SET
+                threshold = COALESCE($1, threshold),
+                webhook_url = COALESCE($2, webhook_url),
+                webhook_hash = COALESCE($3, webhook_hash),
+                webhook_hint = COALESCE($4, webhook_hint),
+                email = COALESCE($5, email),
+                email_hash = COALESCE($6, email_hash),
+                email_hint = COALESCE($7, email_hint)
+            WHERE alert_id = $8 AND user_id = $9",
+        )
+        .bind(threshold)
+        .bind(webhook_url)
+        .bind(webhook_hash)
+        .bind(webhook_hint)
+        .bind(email)
+        .bind(email_hash)
+        .bind(email_hint)
+        .bind(alert_id)
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await?;
+        found(result.rows_affected())?;
+        if detail.is_some() {
+            record_event(&mut transaction, alert_id, actor, EventKind::Edited, detail).await?;
+        }
+        if rearm {
+            // Only alerts that already triggered are re-armed, and recorded as such.
+            let rearmed = sqlx::query("UPDATE alerts SET active = TRUE, stage = 0 WHERE alert_id = $1 AND (NOT active OR stage > 0)")
+                .bind(alert_id)
+                .execute(&mut *transaction)
+                .await?;
+            if rearmed.rows_affected() > 0 {
+                record_event(&mut transaction, alert_id, actor, EventKind::Rearmed, None).await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn set_alert_paused_until(&self, user_id: i64, alert_id: i64, paused_until: Option<i64>, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        let result = sqlx::query("UPDATE alerts SET paused_until = $1 WHERE alert_id = $2 AND user_id = $3 AND deleted_at IS NULL")
+            .bind(paused_until)
+            .bind(alert_id)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        found(result.rows_affected())?;
+        let (kind, detail) = paused_event(paused_until);
+        record_event(&mut transaction, alert_id, actor, kind, detail).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn set_alert_stage(&self, alert_id: i64, stage: i64, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        sqlx::query("UPDATE alerts SET stage = $1 WHERE alert_id = $2")
+            .bind(stage)
+            .bind(alert_id)
+            .execute(&mut *transaction)
+            .await?;
+        record_event(&mut transaction, alert_id, actor, EventKind::Triggered, Some(format!("stage {stage}"))).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_user_alerts_by_cooler(&self, user_id: i64, cooler: Address, loan_id: Option<i64>, actor: Actor) -> RegistryResult<u64> {
+        let mut transaction = self.database.begin().await?;
+        let alert_ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT alert_id FROM alerts WHERE user_id = $1 AND cooler = $2 AND ($3::BIGINT IS NULL OR loan_id = $3) AND deleted_at IS NULL",
+        )
+            .bind(user_id)
+            .bind(canonical_address(&cooler))
+            .bind(loan_id)
+            .fetch_all(&mut *transaction)
+            .await?;
+        found(alert_ids.len() as u64)?;
+        for alert_id in alert_ids.iter() {
+            sqlx::query("UPDATE alerts SET deleted_at = $1 WHERE alert_id = $2")
+                .bind(now())
+                .bind(alert_id)
+                .execute(&mut *transaction)
+                .await?;
+            record_event(&mut transaction, *alert_id, actor, EventKind::Deleted, None).await?;
+        }
+        transaction.commit().await?;
+        Ok(alert_ids.len() as u64)
+    }
+
+    async fn delete_alert(&self, user_id: i64, alert_id: i64, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        let result = sqlx::query("UPDATE alerts SET deleted_at = $1 WHERE alert_id = $2 AND user_id = $3 AND deleted_at IS NULL")
+            .bind(now())
+            .bind(alert_id)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        found(result.rows_affected())?;
+        record_event(&mut transaction, alert_id, actor, EventKind::Deleted, None).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn restore_alerts(&self, user_id: i64, cooler: Option<Address>, loan_id: Option<i64>, quota: &AlertQuota, actor: Actor) -> RegistryResult<RestoredAlerts> {
+        let mut transaction = self.database.begin().await?;
+        let deleted: Vec<SqlxAlertDB> = sqlx::query_as(
+            "SELECT * FROM alerts WHERE user_id = $1 AND deleted_at IS NOT NULL AND ($2::TEXT IS NULL OR cooler = $2) AND ($3::BIGINT IS NULL OR loan_id = $3) ORDER BY alert_id",
+        )
+        .bind(user_id)
+        .bind(cooler.as_ref().map(canonical_address))
+        .bind(loan_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut outcome = RestoredAlerts::default();
+        for alert in deleted {
+            let duplicate: Option<i64> = sqlx::query_scalar(
+                "SELECT alert_id FROM alerts WHERE user_id = $1 AND cooler = $2 AND loan_id = $3 AND COALESCE(webhook_hash, '') = COALESCE($4, '') AND COALESCE(email_hash, '') = COALESCE($5, '') AND deleted_at IS NULL",
+            )
+            .bind(alert.user_id)
+            .bind(&alert.cooler)
+            .bind(alert.loan_id)
+            .bind(&alert.webhook_hash)
+            .bind(&alert.email_hash)
+            .fetch_optional(&mut *transaction)
+            .await?;
+            if duplicate.is_some() {
+                outcome.duplicates += 1;
+                continue;
+            }
+            let user_alerts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alerts WHERE user_id = $1 AND deleted_at IS NULL")
+                .bind(user_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+            let mut over_quota = user_alerts >= quota.per_user;
+            if let Some(guild_id) = alert.guild_id {
+                let guild_alerts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alerts WHERE guild_id = $1 AND deleted_at IS NULL")
+                    .bind(guild_id)
+                    .fetch_one(&mut *transaction)
+                    .await?;
+                over_quota |= guild_alerts >= quota.per_guild;
+            }
+            if over_quota {
+                outcome.over_quota += 1;
+                continue;
+            }
+
+            sqlx::query("UPDATE alerts SET deleted_at = NULL WHERE alert_id = $1")
+                .bind(alert.alert_id)
+                .execute(&mut *transaction)
+                .await?;
+            record_event(&mut transaction, alert.alert_id, actor, EventKind::Restored, None).await?;
+            outcome.restored += 1;
+        }
+        transaction.commit().await?;
+        Ok(outcome)
+    }
+
+    async fn purge_deleted_alerts(&self, deleted_before: i64, actor: Actor) -> RegistryResult<u64> {
+        let mut transaction = self.database.begin().await?;
+        sqlx::query(
+            "INSERT INTO alert_events (alert_id, user_id, guild_id, cooler, loan_id, actor_id, source, kind, detail, created_at)
+            SELECT alert_id, user_id, guild_id, cooler, loan_id, $1, $2, $3, NULL, $4 FROM alerts WHERE deleted_at < $5",
+        )
+        .bind(actor.user_id)
+        .bind(actor.source.as_str())
+        .bind(EventKind::Purged.as_str())
+        .bind(now())
+        .bind(deleted_before)
+        .execute(&mut *transaction)
+        .await?;
+        let result = sqlx::query("DELETE FROM alerts WHERE deleted_at < $1")
+            .bind(deleted_before)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_user_data(&self, user_id: i64) -> RegistryResult<UserData> {
+        let (alerts, deleted_alerts, webhooks, emails, guilds): (i64, i64, i64, i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*) FILTER (WHERE deleted_at IS NULL), COUNT(*) FILTER (WHERE deleted_at IS NOT NULL),
+                COUNT(DISTINCT webhook_hash), COUNT(DISTINCT email_hash), COUNT(DISTINCT guild_id)
+            FROM alerts WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.database)
+        .await?;
+        let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM alert_events WHERE user_id = $1 OR actor_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.database)
+            .await?;
+        Ok(UserData { alerts, deleted_alerts, webhooks, emails, guilds, events })
+    }
+
+    async fn forget_user(&self, user_id: i64) -> RegistryResult<ForgottenUser> {
+        let mut transaction = self.database.begin().await?;
+        let alerts = sqlx::query("DELETE FROM alerts WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        let events = sqlx::query("DELETE FROM alert_events WHERE user_id = $1 OR actor_id = $1")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(ForgottenUser { alerts: alerts.rows_affected(), events: events.rows_affected() })
+    }
+
+    async fn get_alert_events(&self, filter: &EventFilter, limit: i64) -> RegistryResult<Vec<AlertEvent>> {
+        let events = sqlx::query_as(
+            "SELECT * FROM alert_events
+            WHERE ($1::BIGINT IS NULL OR alert_id = $1) AND ($2::BIGINT IS NULL OR user_id = $2) AND ($3::BIGINT IS NULL OR guild_id = $3)
+            ORDER BY event_id DESC LIMIT $4",
+        )
+        .bind(filter.alert_id)
+        .bind(filter.user_id)
+        .bind(filter.guild_id)
+        .bind(limit)
+        .fetch_all(&self.database)
+        .await?;
+        Ok(events)
+    }
+
+    async fn get_user_alerts(&self, user_id: i64, filter: &AlertFilter, limit: i64, offset: i64) -> RegistryResult<Vec<AlertDB>> {
+        let alerts: Vec<SqlxAlertDB> = sqlx::query_as(
+            "SELECT * FROM alerts WHERE user_id = $1 AND deleted_at IS NULL AND ($2::TEXT IS NULL OR cooler = $2) AND ($3::BOOLEAN IS NULL OR active = $3) ORDER BY alert_id LIMIT $4 OFFSET $5",
+        )
+        .bind(user_id)
+        .bind(filter.cooler.as_ref().map(canonical_address))
+        .bind(filter.active)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.database)
+        .await?;
+        Ok(alerts.into_iter().map(|alert| alert.into()).collect())
+    }
+
+    async fn get_user_coolers(&self, user_id: i64) -> RegistryResult<Vec<Address>> {
+        let coolers: Vec<String> = sqlx::query_scalar("SELECT DISTINCT cooler FROM alerts WHERE user_id = $1 AND deleted_at IS NULL ORDER BY cooler")
+            .bind(user_id)
+            .fetch_all(&self.database)
+            .await?;
+        Ok(coolers.into_iter().filter_map(|cooler| cooler.parse().ok()).collect())
+    }
+
+    async fn get_user_loan_ids(&self, user_id: i64, cooler: Address) -> RegistryResult<Vec<i64>> {
+        let loan_ids = sqlx::query_scalar("SELECT DISTINCT loan_id FROM alerts WHERE user_id = $1 AND cooler = $2 AND deleted_at IS NULL ORDER BY loan_id")
+            .bind(user_id)
+            .bind(canonical_address(&cooler))
+            .fetch_all(&self.database)
+            .await?;
+        Ok(loan_ids)
+    }
+
+    async fn count_filtered_user_alerts(&self, user_id: i64, filter: &AlertFilter) -> RegistryResult<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM alerts WHERE user_id = $1 AND deleted_at IS NULL AND ($2::TEXT IS NULL OR cooler = $2) AND ($3::BOOLEAN IS NULL OR active = $3)",
+        )
+        .bind(user_id)
+        .bind(filter.cooler.as_ref().map(canonical_address))
+        .bind(filter.active)
+        .fetch_one(&self.database)
+        .await?;
+        Ok(count)
+    }
+
+    async fn get_user_alert(&self, user_id: i64, alert_id: i64) -> RegistryResult<AlertDB> {
+        let alert: SqlxAlertDB = sqlx::query_as("SELECT * FROM alerts WHERE alert_id = $1 AND user_id = $2 AND deleted_at IS NULL")
+            .bind(alert_id)
+            .bind(user_id)
+            .fetch_one(&self.database)
+            .await?;
+        Ok(alert.into())
+    }
+
+    async fn get_all_alerts(&self) -> RegistryResult<Vec<AlertDB>> {
+        let alerts: Vec<SqlxAlertDB> = sqlx::query_as("SELECT * FROM alerts ORDER BY alert_id")
+            .fetch_all(&self.database)
+            .await?;
+        Ok(alerts.into_iter().map(|alert| alert.into()).collect())
+    }
+
+    async fn get_active_alerts(&self) -> RegistryResult<Vec<AlertDB>> {
+        let alerts: Vec<SqlxAlertDB> = sqlx::query_as("SELECT * FROM alerts WHERE active AND deleted_at IS NULL AND (paused_until IS NULL OR paused_until <= $1) ORDER BY alert_id")
+            .bind(now())
+            .fetch_all(&self.database)
+            .await?;
+        Ok(alerts.into_iter().map(|alert| alert.into()).collect())
+    }
+
+    async fn get_next_resume_time(&self) -> RegistryResult<Option<i64>> {
+        let next_resume = sqlx::query_scalar("SELECT MIN(paused_until) FROM alerts WHERE active AND deleted_at IS NULL AND paused_until > $1")
+            .bind(now())
+            .fetch_one(&self.database)
+            .await?;
+        Ok(next_resume)
+    }
+
+    async fn get_public_replies(&self, guild_id: i64) -> RegistryResult<bool> {
+        let public_replies: Option<bool> = sqlx::query_scalar("SELECT public_replies FROM guild_settings WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_optional(&self.database)
+            .await?;
+        Ok(public_replies.unwrap_or(false))
+    }
+
+    async fn set_public_replies(&self, guild_id: i64, public_replies: bool) -> RegistryResult<()> {
+        sqlx::query("INSERT INTO guild_settings (guild_id, public_replies) VALUES ($1, $2) ON CONFLICT (guild_id) DO UPDATE SET public_replies = excluded.public_replies")
+            .bind(guild_id)
+            .bind(public_replies)
+            .execute(&self.database)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_registered_commands(&self, scope: &str) -> RegistryResult<Option<String>> {
+        let definition = sqlx::query_scalar("SELECT definition FROM command_registrations WHERE scope = $1")
+            .bind(scope)
+            .fetch_optional(&self.database)
+            .await?;
+        Ok(definition)
+    }
+
+    async fn set_registered_commands(&self, scope: &str, definition: &str) -> RegistryResult<()> {
+        sqlx::query("INSERT INTO command_registrations (scope, definition) VALUES ($1, $2) ON CONFLICT (scope) DO UPDATE SET definition = excluded.definition")
+            .bind(scope)
+            .bind(definition)
+            .execute(&self.database)
+            .await?;
+        Ok(())
+    }
+}
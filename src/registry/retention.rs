@@ -0,0 +1,45 @@
+use std::time::Duration;
+use tokio::time::sleep;
+use crate::duration::now;
+use crate::registry::Registry;
+use crate::registry::types::Actor;
+
+const DEFAULT_RETENTION_DAYS: i64 = 7;
+const DAY: i64 = 24 * 3600;
+
+// How often the alerts past their retention window are looked for.
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// How long deleted alerts are kept, so that a mistaken deletion can be undone with /restore_alerts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Retention {
+    pub days: i64,
+}
+
+impl Retention {
+    // Reads the number of days from the `DELETED_ALERTS_RETENTION_DAYS` env variable, defaulting to a week.
+    pub fn from_env() -> Self {
+        let days = match std::env::var("DELETED_ALERTS_RETENTION_DAYS") {
+            Ok(days) if !days.is_empty() => days.parse().expect("invalid DELETED_ALERTS_RETENTION_DAYS"),
+            _ => DEFAULT_RETENTION_DAYS,
+        };
+        Self { days }
+    }
+
+    // Alerts deleted before this timestamp can be purged.
+    pub fn purge_before(&self, now: i64) -> i64 {
+        now - self.days * DAY
+    }
+}
+
+// Removes for good the alerts deleted longer ago than the retention window, every hour.
+pub async fn purge_deleted_alerts(registry: Registry, retention: Retention) {
+    loop {
+        match registry.purge_deleted_alerts(retention.purge_before(now()), Actor::PURGE).await {
+            Ok(0) => (),
+            Ok(purged) => println!("Purged {purged} deleted alerts."),
+            Err(error) => println!("Unable to purge the deleted alerts: {}", error),
+        }
+        sleep(PURGE_INTERVAL).await;
+    }
+}
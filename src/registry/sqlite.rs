@@ -0,0 +1,499 @@
+use std::str::FromStr;
+use async_trait::async_trait;
+use ethers::types::Address;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions};
+use crate::address::canonical_address;
+use crate::duration::now;
+use crate::registry::crypto::SealedField;
+use crate::registry::{check_quota, created_detail, edited_detail, found, paused_event, validate_new_alert, AlertStore};
+use crate::registry::error::{QuotaScope, RegistryResult};
+use crate::registry::types::{SqlxAlertDB, Actor, AlertDB, AlertEvent, AlertFilter, AlertQuota, AlertUpdate, CreatedAlert, EventFilter, EventKind, NewAlert, RestoredAlerts, UserData, ForgottenUser};
+
+// Alerts stored in a SQLite file, the default backend.
+pub struct SqliteStore {
+    database: SqlitePool,
+}
+
+impl SqliteStore {
+    // Opens the database file, creating it if required, and updates its schema to the latest version.
+    pub async fn connect(database_url: &str) -> RegistryResult<Self> {
+        let database = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(SqliteConnectOptions::from_str(database_url)?.create_if_missing(true))
+            .await?;
+        sqlx::migrate!("./migrations").run(&database).await?;
+        Ok(Self { database })
+    }
+}
+
+// Appends an entry to the history of an alert, within the transaction of the change.
+// The alert is copied from its row, so deletions must be recorded before the row is gone.
+async fn record_event(connection: &mut SqliteConnection, alert_id: i64, actor: Actor, kind: EventKind, detail: Option<String>) -> RegistryResult<()> {
+    let (source, kind, now) = (actor.source.as_str(), kind.as_str(), now());
+    sqlx::query!(
+        "INSERT INTO alert_events (alert_id, user_id, guild_id, cooler, loan_id, actor_id, source, kind, detail, created_at)
+        SELECT alert_id, user_id, guild_id, cooler, loan_id, ?, ?, ?, ?, ? FROM alerts WHERE alert_id = ?",
+        actor.user_id,
+        source,
+        kind,
+        detail,
+        now,
+        alert_id
+    )
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl AlertStore for SqliteStore {
+    async fn create_alert(&self, alert: NewAlert, quota: &AlertQuota, actor: Actor) -> RegistryResult<CreatedAlert> {
+        validate_new_alert(&alert)?;
+        let detail = created_detail(&alert);
+        let (webhook_url, webhook_hash, webhook_hint) = SealedField::columns(alert.webhook_url);
+        let (email, email_hash, email_hint) = SealedField::columns(alert.email);
+        let cooler = canonical_address(&alert.cooler);
+        let mut transaction = self.database.begin().await?;
+        let existing = sqlx::query!(
+            "SELECT alert_id as \"alert_id!\" FROM alerts WHERE user_id = ? AND cooler = ? AND loan_id = ? AND COALESCE(webhook_hash, '') = COALESCE(?, '') AND COALESCE(email_hash, '') = COALESCE(?, '') AND deleted_at IS NULL",
+            alert.user_id,
+            cooler,
+            alert.loan_id,
+            webhook_hash,
+            email_hash
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        let created = match existing {
+            Some(row) => {
+                sqlx::query!(
+                    "UPDATE alerts SET threshold = ?, post_expiry = ?, active = 1, stage = 0 WHERE alert_id = ?",
+                    alert.threshold,
+                    alert.post_expiry,
+                    row.alert_id
+                )
+                .execute(&mut *transaction)
+                .await?;
+                let detail = detail.map(|detail| format!("created again, {detail}"));
+                record_event(&mut transaction, row.alert_id, actor, EventKind::Rearmed, detail).await?;
+                CreatedAlert::Updated(row.alert_id)
+            },
+            None => {
+                let user_alerts = sqlx::query!(r#"SELECT COUNT(*) as "count: i64" FROM alerts WHERE user_id = ? AND deleted_at IS NULL"#, alert.user_id)
+                    .fetch_one(&mut *transaction)
+                    .await?;
+                check_quota(QuotaScope::User, user_alerts.count, quota.per_user)?;
+                if let Some(guild_id) = alert.guild_id {
+                    let guild_alerts = sqlx::query!(r#"SELECT COUNT(*) as "count: i64" FROM alerts WHERE guild_id = ? AND deleted_at IS NULL"#, guild_id)
+                        .fetch_one(&mut *transaction)
+                        .await?;
+                    check_quota(QuotaScope::Guild, guild_alerts.count, quota.per_guild)?;
+                }
+
+                let result = sqlx::query!(
+                    "INSERT INTO alerts (user_id, cooler, loan_id, threshold, post_expiry, webhook_url, email, active, guild_id, webhook_hash, email_hash, webhook_hint, email_hint)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, 1, ?, ?, ?, ?, ?)",
+                    alert.user_id,
+                    cooler,
+                    alert.loan_id,
+                    alert.threshold,
+                    alert.post_expiry,
+                    webhook_url,
+                    email,
+                    alert.guild_id,
+                    webhook_hash,
+                    email_hash,
+                    webhook_hint,
+                    email_hint
+                )
+                .execute(&mut *transaction)
+                .await?;
+                record_event(&mut transaction, result.last_insert_rowid(), actor, EventKind::Created, detail).await?;
+                CreatedAlert::New(result.last_insert_rowid())
+            },
+        };
+        transaction.commit().await?;
+        Ok(created)
+    }
+
+    async fn deactivate_alert(&self, alert_id: i64, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        sqlx::query!("UPDATE alerts SET active = 0 WHERE alert_id = ?", alert_id)
+            .execute(&mut *transaction)
+            .await?;
+        record_event(&mut transaction, alert_id, actor, EventKind::Triggered, None).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn update_alert(&self, user_id: i64, alert_id: i64, update: AlertUpdate, actor: Actor) -> RegistryResult<()> {
+        let detail = edited_detail(&update);
+        let AlertUpdate { threshold, webhook_url, email, rearm } = update;
+        let (webhook_url, webhook_hash, webhook_hint) = SealedField::columns(webhook_url);
+        let (email, email_hash, email_hint) = SealedField::columns(email);
+        let mut transaction = self.database.begin().await?;
+        let result = sqlx::query!(
+            "UPDATE alerts SET
+                threshold = COALESCE(?, threshold),
+                webhook_url = COALESCE(?, webhook_url),
+                webhook_hash = COALESCE(?, webhook_hash),
+                webhook_hint = COALESCE(?, webhook_hint),
+                email = COALESCE(?, email),
+                email_hash = COALESCE(?, email_hash),
+                email_hint = COALESCE(?, email_hint)
+            WHERE alert_id = ? AND user_id = ?",
+            threshold,
+            webhook_url,
+            webhook_hash,
+            webhook_hint,
+            email,
+            email_hash,
+            email_hint,
+            alert_id,
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        found(result.rows_affected())?;
+        if detail.is_some() {
+            record_event(&mut transaction, alert_id, actor, EventKind::Edited, detail).await?;
+        }
+        if rearm {
+            // Only alerts that already triggered are re-armed, and recorded as such.
+            let rearmed = sqlx::query!(
+                "UPDATE alerts SET active = 1, stage = 0 WHERE alert_id = ? AND (active = 0 OR stage > 0)",
+                alert_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            if rearmed.rows_affected() > 0 {
+                record_event(&mut transaction, alert_id, actor, EventKind::Rearmed, None).await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn set_alert_paused_until(&self, user_id: i64, alert_id: i64, paused_until: Option<i64>, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        let result = sqlx::query!(
+            "UPDATE alerts SET paused_until = ? WHERE alert_id = ? AND user_id = ? AND deleted_at IS NULL",
+            paused_until,
+            alert_id,
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        found(result.rows_affected())?;
+        let (kind, detail) = paused_event(paused_until);
+        record_event(&mut transaction, alert_id, actor, kind, detail).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn set_alert_stage(&self, alert_id: i64, stage: i64, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        sqlx::query!("UPDATE alerts SET stage = ? WHERE alert_id = ?", stage, alert_id)
+            .execute(&mut *transaction)
+            .await?;
+        record_event(&mut transaction, alert_id, actor, EventKind::Triggered, Some(format!("stage {stage}"))).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_user_alerts_by_cooler(&self, user_id: i64, cooler: Address, loan_id: Option<i64>, actor: Actor) -> RegistryResult<u64> {
+        let cooler = canonical_address(&cooler);
+        let mut transaction = self.database.begin().await?;
+        let rows = sqlx::query!(
+            r#"SELECT alert_id as "alert_id!" FROM alerts WHERE user_id = ? AND cooler = ? AND (? IS NULL OR loan_id = ?) AND deleted_at IS NULL"#,
+            user_id,
+            cooler,
+            loan_id,
+            loan_id
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        found(rows.len() as u64)?;
+        let now = now();
+        for row in rows.iter() {
+            sqlx::query!("UPDATE alerts SET deleted_at = ? WHERE alert_id = ?", now, row.alert_id)
+                .execute(&mut *transaction)
+                .await?;
+            record_event(&mut transaction, row.alert_id, actor, EventKind::Deleted, None).await?;
+        }
+        transaction.commit().await?;
+        Ok(rows.len() as u64)
+    }
+
+    async fn delete_alert(&self, user_id: i64, alert_id: i64, actor: Actor) -> RegistryResult<()> {
+        let mut transaction = self.database.begin().await?;
+        let now = now();
+        let result = sqlx::query!(
+            "UPDATE alerts SET deleted_at = ? WHERE alert_id = ? AND user_id = ? AND deleted_at IS NULL",
+            now,
+            alert_id,
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        found(result.rows_affected())?;
+        record_event(&mut transaction, alert_id, actor, EventKind::Deleted, None).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn restore_alerts(&self, user_id: i64, cooler: Option<Address>, loan_id: Option<i64>, quota: &AlertQuota, actor: Actor) -> RegistryResult<RestoredAlerts> {
+        let cooler = cooler.as_ref().map(canonical_address);
+        let mut transaction = self.database.begin().await?;
+        let deleted = sqlx::query_as!(
+            SqlxAlertDB,
+            "SELECT * FROM alerts WHERE user_id = ? AND deleted_at IS NOT NULL AND (? IS NULL OR cooler = ?) AND (? IS NULL OR loan_id = ?) ORDER BY alert_id",
+            user_id,
+            cooler,
+            cooler,
+            loan_id,
+            loan_id
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut outcome = RestoredAlerts::default();
+        for alert in deleted {
+            let duplicate = sqlx::query!(
+                "SELECT alert_id FROM alerts WHERE user_id = ? AND cooler = ? AND loan_id = ? AND COALESCE(webhook_hash, '') = COALESCE(?, '') AND COALESCE(email_hash, '') = COALESCE(?, '') AND deleted_at IS NULL",
+                alert.user_id,
+                alert.cooler,
+                alert.loan_id,
+                alert.webhook_hash,
+                alert.email_hash
+            )
+            .fetch_optional(&mut *transaction)
+            .await?;
+            if duplicate.is_some() {
+                outcome.duplicates += 1;
+                continue;
+            }
+            let user_alerts = sqlx::query!(r#"SELECT COUNT(*) as "count: i64" FROM alerts WHERE user_id = ? AND deleted_at IS NULL"#, user_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+            let mut over_quota = user_alerts.count >= quota.per_user;
+            if let Some(guild_id) = alert.guild_id {
+                let guild_alerts = sqlx::query!(r#"SELECT COUNT(*) as "count: i64" FROM alerts WHERE guild_id = ? AND deleted_at IS NULL"#, guild_id)
+                    .fetch_one(&mut *transaction)
+                    .await?;
+                over_quota |= guild_alerts.count >= quota.per_guild;
+            }
+            if over_quota {
+                outcome.over_quota += 1;
+                continue;
+            }
+
+            sqlx::query!("UPDATE alerts SET deleted_at = NULL WHERE alert_id = ?", alert.alert_id)
+                .execute(&mut *transaction)
+                .await?;
+            record_event(&mut transaction, alert.alert_id, actor, EventKind::Restored, None).await?;
+            outcome.restored += 1;
+        }
+        transaction.commit().await?;
+        Ok(outcome)
+    }
+
+    async fn purge_deleted_alerts(&self, deleted_before: i64, actor: Actor) -> RegistryResult<u64> {
+        let (source, kind, now) = (actor.source.as_str(), EventKind::Purged.as_str(), now());
+        let mut transaction = self.database.begin().await?;
+        sqlx::query!(
+            "INSERT INTO alert_events (alert_id, user_id, guild_id, cooler, loan_id, actor_id, source, kind, detail, created_at)
+            SELECT alert_id, user_id, guild_id, cooler, loan_id, ?, ?, ?, NULL, ? FROM alerts WHERE deleted_at < ?",
+            actor.user_id,
+            source,
+            kind,
+            now,
+            deleted_before
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let result = sqlx::query!("DELETE FROM alerts WHERE deleted_at < ?", deleted_before)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_user_data(&self, user_id: i64) -> RegistryResult<UserData> {
+        let alerts = sqlx::query!(
+            r#"SELECT COALESCE(SUM(deleted_at IS NULL), 0) as "alerts!: i64", COALESCE(SUM(deleted_at IS NOT NULL), 0) as "deleted_alerts!: i64",
+                COUNT(DISTINCT webhook_hash) as "webhooks!: i64", COUNT(DISTINCT email_hash) as "emails!: i64", COUNT(DISTINCT guild_id) as "guilds!: i64"
+            FROM alerts WHERE user_id = ?"#,
+            user_id
+        )
+        .fetch_one(&self.database)
+        .await?;
+        let events = sqlx::query!(r#"SELECT COUNT(*) as "count: i64" FROM alert_events WHERE user_id = ? OR actor_id = ?"#, user_id, user_id)
+            .fetch_one(&self.database)
+            .await?;
+        Ok(UserData {
+            alerts: alerts.alerts,
+            deleted_alerts: alerts.deleted_alerts,
+            webhooks: alerts.webhooks,
+            emails: alerts.emails,
+            guilds: alerts.guilds,
+            events: events.count,
+        })
+    }
+
+    async fn forget_user(&self, user_id: i64) -> RegistryResult<ForgottenUser> {
+        let mut transaction = self.database.begin().await?;
+        let alerts = sqlx::query!("DELETE FROM alerts WHERE user_id = ?", user_id)
+            .execute(&mut *transaction)
+            .await?;
+        let events = sqlx::query!("DELETE FROM alert_events WHERE user_id = ? OR actor_id = ?", user_id, user_id)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(ForgottenUser { alerts: alerts.rows_affected(), events: events.rows_affected() })
+    }
+
+    async fn get_alert_events(&self, filter: &EventFilter, limit: i64) -> RegistryResult<Vec<AlertEvent>> {
+        let events = sqlx::query_as!(
+            AlertEvent,
+            "SELECT * FROM alert_events WHERE (? IS NULL OR alert_id = ?) AND (? IS NULL OR user_id = ?) AND (? IS NULL OR guild_id = ?)
+            ORDER BY event_id DESC LIMIT ?",
+            filter.alert_id,
+            filter.alert_id,
+            filter.user_id,
+            filter.user_id,
+            filter.guild_id,
+            filter.guild_id,
+            limit
+        )
+        .fetch_all(&self.database)
+        .await?;
+        Ok(events)
+    }
+
+    async fn get_user_alerts(&self, user_id: i64, filter: &AlertFilter, limit: i64, offset: i64) -> RegistryResult<Vec<AlertDB>> {
+        let cooler = filter.cooler.as_ref().map(canonical_address);
+        let alerts = sqlx::query_as!(
+            SqlxAlertDB,
+            // Columns are listed so that their nullability is explicit, as the query planner goes through the destination index.
+            r#"SELECT alert_id as "alert_id!", user_id as "user_id!", cooler as "cooler!", loan_id as "loan_id!", threshold as "threshold!",
+                webhook_url, email, active as "active!", post_expiry as "post_expiry!", stage as "stage!", paused_until, guild_id,
+                webhook_hash, email_hash, webhook_hint, email_hint, deleted_at
+            FROM alerts WHERE user_id = ? AND deleted_at IS NULL AND (? IS NULL OR cooler = ?) AND (? IS NULL OR active = ?) ORDER BY rowid LIMIT ? OFFSET ?"#,
+            user_id,
+            cooler,
+            cooler,
+            filter.active,
+            filter.active,
+            limit,
+            offset
+        )
+        .fetch_all(&self.database)
+        .await?;
+        Ok(alerts.into_iter().map(|alert| alert.into()).collect())
+    }
+
+    async fn get_user_coolers(&self, user_id: i64) -> RegistryResult<Vec<Address>> {
+        let rows = sqlx::query!("SELECT DISTINCT cooler FROM alerts WHERE user_id = ? AND deleted_at IS NULL ORDER BY cooler", user_id)
+            .fetch_all(&self.database)
+            .await?;
+        Ok(rows.into_iter().filter_map(|row| row.cooler.parse().ok()).collect())
+    }
+
+    async fn get_user_loan_ids(&self, user_id: i64, cooler: Address) -> RegistryResult<Vec<i64>> {
+        let cooler = canonical_address(&cooler);
+        let rows = sqlx::query!("SELECT DISTINCT loan_id FROM alerts WHERE user_id = ? AND cooler = ? AND deleted_at IS NULL ORDER BY loan_id", user_id, cooler)
+            .fetch_all(&self.database)
+            .await?;
+        Ok(rows.into_iter().map(|row| row.loan_id).collect())
+    }
+
+    async fn count_filtered_user_alerts(&self, user_id: i64, filter: &AlertFilter) -> RegistryResult<i64> {
+        let cooler = filter.cooler.as_ref().map(canonical_address);
+        let query = sqlx::query!(
+            "SELECT COUNT(*) as count FROM alerts WHERE user_id = ? AND deleted_at IS NULL AND (? IS NULL OR cooler = ?) AND (? IS NULL OR active = ?)",
+            user_id,
+            cooler,
+            cooler,
+            filter.active,
+            filter.active
+        )
+        .fetch_one(&self.database)
+        .await?;
+        Ok(query.count as i64)
+    }
+
+    async fn get_user_alert(&self, user_id: i64, alert_id: i64) -> RegistryResult<AlertDB> {
+        let alert = sqlx::query_as!(SqlxAlertDB, "SELECT * FROM alerts WHERE alert_id = ? AND user_id = ? AND deleted_at IS NULL", alert_id, user_id)
+            .fetch_one(&self.database)
+            .await?;
+        Ok(alert.into())
+    }
+
+    async fn get_all_alerts(&self) -> RegistryResult<Vec<AlertDB>> {
+        let alerts = sqlx::query_as!(SqlxAlertDB, "SELECT * FROM alerts ORDER BY rowid")
+            .fetch_all(&self.database)
+            .await?;
+        Ok(alerts.into_iter().map(|alert| alert.into()).collect())
+    }
+
+    async fn get_active_alerts(&self) -> RegistryResult<Vec<AlertDB>> {
+        let now = now();
+        let alerts = sqlx::query_as!(
+            SqlxAlertDB,
+            r#"SELECT alert_id as "alert_id!", user_id as "user_id!", cooler as "cooler!", loan_id as "loan_id!", threshold as "threshold!",
+                webhook_url, email, active as "active!", post_expiry as "post_expiry!", stage as "stage!", paused_until, guild_id,
+                webhook_hash, email_hash, webhook_hint, email_hint, deleted_at
+            FROM alerts WHERE active = 1 AND deleted_at IS NULL AND (paused_until IS NULL OR paused_until <= ?) ORDER BY rowid"#,
+            now
+        )
+        .fetch_all(&self.database)
+        .await?;
+        Ok(alerts.into_iter().map(|alert| alert.into()).collect())
+    }
+
+    async fn get_next_resume_time(&self) -> RegistryResult<Option<i64>> {
+        let now = now();
+        let query = sqlx::query!("SELECT MIN(paused_until) as next_resume FROM alerts WHERE active = 1 AND deleted_at IS NULL AND paused_until > ?", now)
+            .fetch_one(&self.database)
+            .await?;
+        Ok(query.next_resume)
+    }
+
+    async fn get_public_replies(&self, guild_id: i64) -> RegistryResult<bool> {
+        let row = sqlx::query!("SELECT public_replies FROM guild_settings WHERE guild_id = ?", guild_id)
+            .fetch_optional(&self.database)
+            .await?;
+        Ok(row.map(|row| row.public_replies).unwrap_or(false))
+    }
+
+    async fn set_public_replies(&self, guild_id: i64, public_replies: bool) -> RegistryResult<()> {
+        sqlx::query!(
+            "INSERT INTO guild_settings (guild_id, public_replies) VALUES (?, ?) ON CONFLICT(guild_id) DO UPDATE SET public_replies = excluded.public_replies",
+            guild_id,
+            public_replies
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_registered_commands(&self, scope: &str) -> RegistryResult<Option<String>> {
+        let row = sqlx::query!("SELECT definition FROM command_registrations WHERE scope = ?", scope)
+            .fetch_optional(&self.database)
+            .await?;
+        Ok(row.map(|row| row.definition))
+    }
+
+    async fn set_registered_commands(&self, scope: &str, definition: &str) -> RegistryResult<()> {
+        sqlx::query!(
+            "INSERT INTO command_registrations (scope, definition) VALUES (?, ?) ON CONFLICT(scope) DO UPDATE SET definition = excluded.definition",
+            scope,
+            definition
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+// Behaviour shared by every store, checked against each of them with the same tests.
+// The Postgres tests are ignored by default. Run them with `cargo test -- --ignored` and `TEST_POSTGRES_URL`
+// pointing to a database they can empty.
+use std::sync::Arc;
+use ethers::types::Address;
+use tokio::sync::{Mutex, MutexGuard};
+use crate::address::canonical_address;
+use crate::duration::now;
+use crate::registry::crypto::FieldCipher;
+use crate::registry::error::{QuotaScope, RegistryError};
+use crate::registry::types::{Actor, AlertFilter, AlertQuota, AlertUpdate, CreatedAlert, EventFilter, NewAlert, RestoredAlerts, UserData, ForgottenUser};
+use crate::registry::{memory::MemoryStore, postgres::PostgresStore, sqlite::SqliteStore, Registry};
+
+const QUOTA: AlertQuota = AlertQuota { per_user: 10, per_guild: 10 };
+const ACTOR: Actor = Actor::command(1);
+
+// The Postgres tests share one database, so they run one at a time.
+static POSTGRES: Mutex<()> = Mutex::const_new(());
+
+async fn memory_store() -> Registry {
+    Arc::new(MemoryStore::default())
+}
+
+// Each connection to `sqlite::memory:` opens a new database, with the migrations applied.
+async fn sqlite_store() -> Registry {
+    Arc::new(SqliteStore::connect("sqlite::memory:").await.unwrap())
+}
+
+// Emptied Postgres store, and the lock to keep until the test ends.
+async fn postgres_store() -> (Registry, MutexGuard<'static, ()>) {
+    let database_url = std::env::var("TEST_POSTGRES_URL").expect("TEST_POSTGRES_URL must be set to run the Postgres tests");
+    let guard = POSTGRES.lock().await;
+    let store = PostgresStore::connect(&database_url).await.unwrap();
+    store.truncate().await.unwrap();
+    (Arc::new(store), guard)
+}
+
+macro_rules! store_tests {
+    ($($test:ident),* $(,)?) => {
+        mod memory {
+            $(#[tokio::test]
+            async fn $test() {
+                super::$test(super::memory_store().await).await;
+            })*
+        }
+
+        mod sqlite {
+            $(#[tokio::test]
+            async fn $test() {
+                super::$test(super::sqlite_store().await).await;
+            })*
+        }
+
+        mod postgres {
+            $(#[tokio::test]
+            #[ignore = "needs TEST_POSTGRES_URL"]
+            async fn $test() {
+                let (store, _guard) = super::postgres_store().await;
+                super::$test(store).await;
+            })*
+        }
+    };
+}
+
+store_tests!(
+    alerts_need_a_receiver,
+    users_only_reach_their_own_alerts,
+    lists_and_counts_with_filters,
+    paused_alerts_are_not_monitored,
+    same_destination_updates_the_existing_alert,
+    quotas_limit_new_alerts,
+    changes_are_recorded_in_the_history,
+    deleted_alerts_can_be_restored_until_purged,
+    forgotten_users_leave_no_trace,
+);
+
+fn cipher() -> FieldCipher {
+    FieldCipher::new(&[1; 32], &[])
+}
+
+fn new_alert(user_id: i64, cooler: u8, loan_id: i64) -> NewAlert {
+    NewAlert {
+        user_id,
+        cooler: Address::repeat_byte(cooler),
+        loan_id,
+        threshold: 3600,
+        post_expiry: false,
+        webhook_url: None,
+        email: Some(cipher().seal_email("cooler_alerts@yxz.com")),
+        guild_id: None,
+    }
+}
+
+async fn alerts_need_a_receiver(store: Registry) {
+    let alert = NewAlert { email: None, ..new_alert(1, 0xab, 0) };
+    assert!(matches!(store.create_alert(alert, &QUOTA, ACTOR).await, Err(RegistryError::Validation(_))));
+}
+
+async fn users_only_reach_their_own_alerts(store: Registry) {
+    let CreatedAlert::New(alert_id) = store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+
+    assert!(matches!(store.get_user_alert(2, alert_id).await, Err(RegistryError::NotFound)));
+    assert!(matches!(store.delete_alert(2, alert_id, ACTOR).await, Err(RegistryError::NotFound)));
+    assert!(matches!(store.update_alert(2, alert_id, AlertUpdate { threshold: Some(60), ..AlertUpdate::default() }, ACTOR).await, Err(RegistryError::NotFound)));
+    assert_eq!(store.get_user_alert(1, alert_id).await.unwrap().get_threshold(), 3600);
+}
+
+async fn lists_and_counts_with_filters(store: Registry) {
+    store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap();
+    let CreatedAlert::New(triggered) = store.create_alert(new_alert(1, 0xab, 1), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    store.create_alert(new_alert(1, 0xde, 0), &QUOTA, ACTOR).await.unwrap();
+    store.create_alert(new_alert(2, 0xab, 0), &QUOTA, ACTOR).await.unwrap();
+    store.deactivate_alert(triggered, Actor::LISTENER).await.unwrap();
+
+    let filter = AlertFilter { cooler: Some(Address::repeat_byte(0xab)), active: Some(true) };
+    assert_eq!(store.count_filtered_user_alerts(1, &filter).await.unwrap(), 1);
+    assert_eq!(store.count_filtered_user_alerts(1, &AlertFilter::default()).await.unwrap(), 3);
+    let page = store.get_user_alerts(1, &AlertFilter::default(), 2, 1).await.unwrap();
+    assert_eq!(page.iter().map(|alert| alert.get_loan_id()).collect::<Vec<_>>(), vec![1, 0]);
+    assert_eq!(store.get_user_coolers(1).await.unwrap(), vec![Address::repeat_byte(0xab), Address::repeat_byte(0xde)]);
+}
+
+async fn paused_alerts_are_not_monitored(store: Registry) {
+    let CreatedAlert::New(paused) = store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    store.create_alert(new_alert(1, 0xab, 1), &QUOTA, ACTOR).await.unwrap();
+    let resume_time = now() + 3600;
+    store.set_alert_paused_until(1, paused, Some(resume_time), ACTOR).await.unwrap();
+
+    let active = store.get_active_alerts().await.unwrap();
+    assert_eq!(active.iter().map(|alert| alert.get_loan_id()).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(store.get_next_resume_time().await.unwrap(), Some(resume_time));
+}
+
+async fn same_destination_updates_the_existing_alert(store: Registry) {
+    let CreatedAlert::New(alert_id) = store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    store.deactivate_alert(alert_id, Actor::LISTENER).await.unwrap();
+
+    let again = NewAlert { threshold: 60, ..new_alert(1, 0xab, 0) };
+    assert_eq!(store.create_alert(again, &QUOTA, ACTOR).await.unwrap(), CreatedAlert::Updated(alert_id));
+    let alert = store.get_user_alert(1, alert_id).await.unwrap();
+    assert_eq!(alert.get_threshold(), 60);
+    assert_eq!(store.count_filtered_user_alerts(1, &AlertFilter::default()).await.unwrap(), 1);
+
+    // Another destination is a separate alert, which can't be edited into a copy of the first one.
+    let webhook = NewAlert { webhook_url: Some(cipher().seal_webhook_url("https://discord.com/api/webhooks/1/a")), ..new_alert(1, 0xab, 0) };
+    let CreatedAlert::New(other_id) = store.create_alert(webhook, &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    assert!(matches!(store.update_alert(1, alert_id, AlertUpdate { webhook_url: Some(cipher().seal_webhook_url("https://discord.com/api/webhooks/1/a")), ..AlertUpdate::default() }, ACTOR).await, Err(RegistryError::Duplicate)));
+    assert_ne!(alert_id, other_id);
+}
+
+async fn quotas_limit_new_alerts(store: Registry) {
+    let quota = AlertQuota { per_user: 2, per_guild: 3 };
+    let in_guild = |user_id, loan_id| NewAlert { guild_id: Some(7), ..new_alert(user_id, 0xab, loan_id) };
+    store.create_alert(in_guild(1, 0), &quota, ACTOR).await.unwrap();
+    store.create_alert(in_guild(1, 1), &quota, ACTOR).await.unwrap();
+
+    let error = store.create_alert(in_guild(1, 2), &quota, ACTOR).await.unwrap_err();
+    assert!(matches!(error, RegistryError::QuotaExceeded { scope: QuotaScope::User, used: 2, limit: 2 }));
+    // Updating an existing alert doesn't need room for a new one.
+    assert!(matches!(store.create_alert(in_guild(1, 1), &quota, ACTOR).await, Ok(CreatedAlert::Updated(_))));
+
+    store.create_alert(in_guild(2, 0), &quota, ACTOR).await.unwrap();
+    let error = store.create_alert(in_guild(3, 0), &quota, ACTOR).await.unwrap_err();
+    assert!(matches!(error, RegistryError::QuotaExceeded { scope: QuotaScope::Guild, used: 3, limit: 3 }));
+    assert!(store.create_alert(new_alert(3, 0xab, 0), &quota, ACTOR).await.is_ok());
+}
+
+async fn changes_are_recorded_in_the_history(store: Registry) {
+    let CreatedAlert::New(alert_id) = store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    // The alert is still armed, so only the edit is recorded.
+    store.update_alert(1, alert_id, AlertUpdate { threshold: Some(60), rearm: true, ..AlertUpdate::default() }, ACTOR).await.unwrap();
+    store.deactivate_alert(alert_id, Actor::LISTENER).await.unwrap();
+    store.update_alert(1, alert_id, AlertUpdate { rearm: true, ..AlertUpdate::default() }, ACTOR).await.unwrap();
+    assert!(store.get_user_alert(1, alert_id).await.unwrap().is_active());
+    // Failed changes leave no trace.
+    assert!(store.delete_alert(2, alert_id, Actor::command(2)).await.is_err());
+    store.delete_alert(1, alert_id, Actor::component(1)).await.unwrap();
+
+    let filter = EventFilter { alert_id: Some(alert_id), ..EventFilter::default() };
+    let events = store.get_alert_events(&filter, 10).await.unwrap();
+    let summary: Vec<(&str, &str, Option<i64>)> = events
+        .iter()
+        .map(|event| (event.kind.as_str(), event.source.as_str(), event.actor_id))
+        .collect();
+    assert_eq!(summary, vec![
+        ("deleted", "component", Some(1)),
+        ("rearmed", "command", Some(1)),
+        ("triggered", "listener", None),
+        ("edited", "command", Some(1)),
+        ("created", "command", Some(1)),
+    ]);
+    assert_eq!(events[3].detail.as_deref(), Some("threshold 1m"));
+    // The history outlives the alert.
+    assert_eq!(events[0].cooler, canonical_address(&Address::repeat_byte(0xab)));
+    assert_eq!(store.get_alert_events(&filter, 2).await.unwrap().len(), 2);
+}
+
+async fn deleted_alerts_can_be_restored_until_purged(store: Registry) {
+    let CreatedAlert::New(alert_id) = store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    store.create_alert(new_alert(1, 0xab, 1), &QUOTA, ACTOR).await.unwrap();
+    store.delete_user_alerts_by_cooler(1, Address::repeat_byte(0xab), None, ACTOR).await.unwrap();
+    assert_eq!(store.count_filtered_user_alerts(1, &AlertFilter::default()).await.unwrap(), 0);
+    assert!(store.get_active_alerts().await.unwrap().is_empty());
+
+    // The first alert was created again in the meantime, so restoring it would make a copy.
+    let CreatedAlert::New(again) = store.create_alert(new_alert(1, 0xab, 0), &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    assert_ne!(again, alert_id);
+    let restored = store.restore_alerts(1, Some(Address::repeat_byte(0xab)), None, &QUOTA, ACTOR).await.unwrap();
+    assert_eq!(restored, RestoredAlerts { restored: 1, duplicates: 1, over_quota: 0 });
+    assert_eq!(store.count_filtered_user_alerts(1, &AlertFilter::default()).await.unwrap(), 2);
+
+    store.delete_alert(1, again, ACTOR).await.unwrap();
+    assert_eq!(store.purge_deleted_alerts(now() - 60, Actor::PURGE).await.unwrap(), 0);
+    assert_eq!(store.purge_deleted_alerts(now() + 1, Actor::PURGE).await.unwrap(), 2);
+    assert_eq!(store.get_all_alerts().await.unwrap().len(), 1);
+    let restored = store.restore_alerts(1, None, None, &QUOTA, ACTOR).await.unwrap();
+    assert_eq!(restored, RestoredAlerts::default());
+}
+
+async fn forgotten_users_leave_no_trace(store: Registry) {
+    let CreatedAlert::New(alert_id) = store.create_alert(NewAlert { guild_id: Some(7), ..new_alert(1, 0xab, 0) }, &QUOTA, ACTOR).await.unwrap() else { panic!() };
+    store.create_alert(new_alert(1, 0xab, 1), &QUOTA, ACTOR).await.unwrap();
+    store.create_alert(new_alert(2, 0xab, 0), &QUOTA, Actor::command(2)).await.unwrap();
+    store.delete_alert(1, alert_id, ACTOR).await.unwrap();
+
+    let data = store.get_user_data(1).await.unwrap();
+    assert_eq!(data, UserData { alerts: 1, deleted_alerts: 1, webhooks: 0, emails: 1, guilds: 1, events: 3 });
+    assert_eq!(store.forget_user(1).await.unwrap(), ForgottenUser { alerts: 2, events: 3 });
+    assert_eq!(store.get_user_data(1).await.unwrap(), UserData::default());
+    assert_eq!(store.get_user_data(2).await.unwrap().alerts, 1);
+}
@@ -1,22 +1,46 @@
-use std::error::Error;
-use std::fmt;
+use ethers::types::Address;
+use crate::duration::now;
+use crate::registry::crypto::SealedField;
 
-// Custom errors
-#[derive(Debug)]
-pub struct ErrorDB(String);
+// Value of `paused_until` for alerts paused until the user resumes them.
+pub const PAUSED_INDEFINITELY: i64 = i64::MAX;
 
-impl ErrorDB {
-    pub fn new(message: &str) -> Self {
-        ErrorDB(message.to_string())
-    }
+// Filters applied when listing the alerts of a user.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlertFilter {
+    pub cooler: Option<Address>,
+    // `Some(true)` only keeps the alerts waiting to trigger, `Some(false)` only the triggered ones.
+    pub active: Option<bool>,
+}
+
+// Fields of an alert about to be created.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewAlert {
+    pub user_id: i64,
+    pub cooler: Address,
+    pub loan_id: i64,
+    // Threshold in seconds.
+    pub threshold: i64,
+    pub post_expiry: bool,
+    pub webhook_url: Option<SealedField>,
+    pub email: Option<SealedField>,
+    // Guild where the alert is created, `None` in DMs.
+    pub guild_id: Option<i64>,
 }
 
-impl Error for ErrorDB {}
+// Maximum number of alerts a user, or all the users of a guild, can have.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlertQuota {
+    pub per_user: i64,
+    pub per_guild: i64,
+}
 
-impl fmt::Display for ErrorDB {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
+// Outcome of `create_alert`. An alert with the same user, cooler, loan and destination is updated instead of copied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CreatedAlert {
+    New(i64),
+    // The existing alert got the new threshold and is waiting to trigger again.
+    Updated(i64),
 }
 
 // Alert Struct for the DB entries.
@@ -25,12 +49,18 @@ impl fmt::Display for ErrorDB {
 pub struct AlertDB {
     alert_id: i64,
     user_id: i64,
-    cooler: String,
+    cooler: Address,
     loan_id: i64,
     threshold: i64,
+    // Encrypted receivers, see `FieldCipher`.
     webhook_url: Option<String>,
     email: Option<String>,
     active: bool,
+    post_expiry: bool,
+    stage: i64,
+    paused_until: Option<i64>,
+    webhook_hint: Option<String>,
+    email_hint: Option<String>,
 }
 
 impl AlertDB {
@@ -42,8 +72,8 @@ impl AlertDB {
         self.user_id
     }
 
-    pub fn get_cooler(&self) -> &str {
-        &self.cooler
+    pub fn get_cooler(&self) -> Address {
+        self.cooler
     }
 
     pub fn get_loan_id(&self) -> i64 {
@@ -54,39 +84,67 @@ impl AlertDB {
         self.threshold
     }
 
-    pub fn get_webhook_url(&self) -> Option<&str> {
-        match &self.webhook_url {
-            Some(webhook_url) => Some(webhook_url),
-            None => None,
-        }
+    // Encrypted webhook URL, only decrypted to deliver notifications.
+    pub fn get_encrypted_webhook_url(&self) -> Option<&str> {
+        self.webhook_url.as_deref()
     }
 
-    pub fn get_email(&self) -> Option<&str> {
-        match &self.email {
-            Some(email) => Some(email),
-            None => None,
-        }
+    // Encrypted email, only decrypted to deliver notifications.
+    pub fn get_encrypted_email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    // Masked webhook URL that can be shown to the user. Missing for alerts stored before encryption was introduced.
+    pub fn get_webhook_hint(&self) -> Option<&str> {
+        self.webhook_hint.as_deref()
+    }
+
+    pub fn get_email_hint(&self) -> Option<&str> {
+        self.email_hint.as_deref()
     }
 
     pub fn is_active(&self) -> bool {
-        println!("Active: {}", self.active);
         self.active
     }
+
+    pub fn has_post_expiry(&self) -> bool {
+        self.post_expiry
+    }
+
+    pub fn get_stage(&self) -> i64 {
+        self.stage
+    }
+
+    // Timestamp until which the alert is muted, if it is paused or snoozed right now.
+    pub fn get_paused_until(&self) -> Option<i64> {
+        self.paused_until.filter(|paused_until| *paused_until > now())
+    }
 }
 
 // Alert Struct for the DB entries.
 // Only used when directly reading from the DB.
 // All its attributes are public so that new entities can be created by sqlx.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, sqlx::FromRow)]
 pub struct SqlxAlertDB {
     pub alert_id: i64,
     pub user_id: i64,
+    // Canonical form of the address, see `canonical_address`.
     pub cooler: String,
     pub loan_id: i64,
     pub threshold: i64,
     pub webhook_url: Option<String>,
     pub email: Option<String>,
     pub active: bool,
+    pub post_expiry: bool,
+    pub stage: i64,
+    pub paused_until: Option<i64>,
+    pub guild_id: Option<i64>,
+    pub webhook_hash: Option<String>,
+    pub email_hash: Option<String>,
+    pub webhook_hint: Option<String>,
+    pub email_hint: Option<String>,
+    // When the alert was deleted. Deleted alerts can be restored until they are purged.
+    pub deleted_at: Option<i64>,
 }
 
 impl From<SqlxAlertDB> for AlertDB {
@@ -94,12 +152,169 @@ impl From<SqlxAlertDB> for AlertDB {
         AlertDB {
             alert_id: item.alert_id,
             user_id: item.user_id,
-            cooler: item.cooler,
+            // Only valid addresses are stored, which the migrations enforced for the older alerts.
+            cooler: item.cooler.parse().unwrap_or_default(),
             loan_id: item.loan_id,
             threshold: item.threshold,
             webhook_url: item.webhook_url,
             email: item.email,
             active: item.active,
+            post_expiry: item.post_expiry,
+            stage: item.stage,
+            paused_until: item.paused_until,
+            webhook_hint: item.webhook_hint,
+            email_hint: item.email_hint,
+        }
+    }
+}
+
+// Outcome of `restore_alerts`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RestoredAlerts {
+    pub restored: u64,
+    // Alerts left deleted because the user created them again since then.
+    pub duplicates: u64,
+    // Alerts left deleted because the user or the guild reached the quota since then.
+    pub over_quota: u64,
+}
+
+// Summary of what is stored about a user, shown by /my_data.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UserData {
+    pub alerts: i64,
+    // Deleted alerts kept until the end of the retention window.
+    pub deleted_alerts: i64,
+    // Distinct receivers, compared by their hash.
+    pub webhooks: i64,
+    pub emails: i64,
+    pub guilds: i64,
+    // Entries of the history of the user's alerts, or made by the user.
+    pub events: i64,
+}
+
+// Outcome of `forget_user`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ForgottenUser {
+    pub alerts: u64,
+    pub events: u64,
+}
+
+// Changes to an alert applied by `update_alert`. Missing fields are left as they are.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlertUpdate {
+    // Threshold in seconds.
+    pub threshold: Option<i64>,
+    pub webhook_url: Option<SealedField>,
+    pub email: Option<SealedField>,
+    // Makes the alert active again, waiting for its threshold to be crossed.
+    // Alerts that are still waiting for it are left as they are.
+    pub rearm: bool,
+}
+
+// Kind of change recorded in the history of an alert.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventKind {
+    Created,
+    Edited,
+    Paused,
+    Resumed,
+    // The listener notified the user, or stopped following a loan that was settled.
+    Triggered,
+    Rearmed,
+    Deleted,
+    Restored,
+    // The alert was deleted long enough ago to be removed for good.
+    Purged,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Created => "created",
+            EventKind::Edited => "edited",
+            EventKind::Paused => "paused",
+            EventKind::Resumed => "resumed",
+            EventKind::Triggered => "triggered",
+            EventKind::Rearmed => "rearmed",
+            EventKind::Deleted => "deleted",
+            EventKind::Restored => "restored",
+            EventKind::Purged => "purged",
         }
     }
-}
\ No newline at end of file
+}
+
+// Where a change came from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventSource {
+    // A slash command, or the form it opens.
+    Command,
+    // A button or menu attached to a message of the bot.
+    Component,
+    Listener,
+    // A maintenance command run by the operator.
+    Cli,
+    // The background job removing the deleted alerts once their retention window is over.
+    Purge,
+}
+
+impl EventSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventSource::Command => "command",
+            EventSource::Component => "component",
+            EventSource::Listener => "listener",
+            EventSource::Cli => "cli",
+            EventSource::Purge => "purge",
+        }
+    }
+}
+
+// Who changed an alert: a Discord user, or the bot itself when `user_id` is missing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Actor {
+    pub user_id: Option<i64>,
+    pub source: EventSource,
+}
+
+impl Actor {
+    pub const LISTENER: Actor = Actor { user_id: None, source: EventSource::Listener };
+    pub const CLI: Actor = Actor { user_id: None, source: EventSource::Cli };
+    pub const PURGE: Actor = Actor { user_id: None, source: EventSource::Purge };
+
+    pub const fn command(user_id: i64) -> Self {
+        Self { user_id: Some(user_id), source: EventSource::Command }
+    }
+
+    pub const fn component(user_id: i64) -> Self {
+        Self { user_id: Some(user_id), source: EventSource::Component }
+    }
+}
+
+// Entry of the history of the alerts. Entries are only ever added, and outlive the alert they refer to.
+// The kind and source are kept as recorded, see `EventKind` and `EventSource`.
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct AlertEvent {
+    pub event_id: i64,
+    pub alert_id: i64,
+    // Owner of the alert.
+    pub user_id: i64,
+    pub guild_id: Option<i64>,
+    // Canonical form of the address, see `canonical_address`.
+    pub cooler: String,
+    pub loan_id: i64,
+    // User who made the change, missing for changes made by the bot.
+    pub actor_id: Option<i64>,
+    pub source: String,
+    pub kind: String,
+    pub detail: Option<String>,
+    pub created_at: i64,
+}
+
+// Filters applied when reading the history of the alerts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventFilter {
+    pub alert_id: Option<i64>,
+    // Owner of the alerts.
+    pub user_id: Option<i64>,
+    pub guild_id: Option<i64>,
+}